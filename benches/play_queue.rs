@@ -0,0 +1,123 @@
+//! Measures how long it takes to queue audio from many threads at once,
+//! and to play thousands of queued requests in a single frame.
+//!
+//! Run with `cargo bench --bench play_queue`.
+
+use {
+    bevy::{
+        app::App,
+        asset::{AddAsset, AssetPlugin, Assets, Handle},
+        core::TaskPoolPlugin,
+        ecs::{
+            schedule::{IntoSystemConfigs, Schedule},
+            system::ResMut,
+        },
+    },
+    bevy_oddio::{
+        builtins::sine::{self, Sine},
+        oddio::{self, Mixer, Sample},
+        output::{
+            clean_stopped_audio_sinks, play_queued_audio, AudioOutput, AudioSink, AudioSinks,
+        },
+        Audio,
+    },
+    criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput},
+    std::{
+        thread,
+        time::{Duration, Instant},
+    },
+};
+
+const REQUESTS: usize = 100_000;
+
+fn enqueue(c: &mut Criterion) {
+    let mut group = c.benchmark_group("enqueue");
+    group.sample_size(10);
+    for threads in [1, 2, 4, 8, 16] {
+        let requests = REQUESTS / threads * threads;
+        group.throughput(Throughput::Elements(requests as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(threads),
+            &threads,
+            |b, &threads| {
+                b.iter_custom(|iters| {
+                    let mut elapsed = Duration::ZERO;
+                    for _ in 0..iters {
+                        let audio = Audio::<Sample, Sine>::default();
+                        let start = Instant::now();
+                        thread::scope(|scope| {
+                            for _ in 0..threads {
+                                scope.spawn(|| {
+                                    for _ in 0..REQUESTS / threads {
+                                        let _ = audio.play(
+                                            Handle::default(),
+                                            sine::Settings::new(0.0, 440.0),
+                                        );
+                                    }
+                                });
+                            }
+                        });
+                        elapsed += start.elapsed();
+                        assert_eq!(audio.queued(), requests);
+                    }
+                    elapsed
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+fn stop_all_sinks(mut sinks: ResMut<Assets<AudioSink<Sine>>>) {
+    for (_, sink) in sinks.iter_mut() {
+        sink.stop();
+    }
+}
+
+fn play_queued(c: &mut Criterion) {
+    let mut group = c.benchmark_group("play_queued_audio");
+    group.sample_size(10);
+    for requests in [1_000, 10_000, 100_000] {
+        let (mixer_handle, mixer) = oddio::split(Mixer::new());
+        let mut app = App::new();
+        app.add_plugins((TaskPoolPlugin::default(), AssetPlugin::default()))
+            .add_asset::<Sine>()
+            .add_asset::<AudioSink<Sine>>()
+            .insert_resource(AudioOutput::with_mixer(mixer_handle))
+            .init_resource::<Audio<Sample, Sine>>()
+            .init_resource::<AudioSinks<Sine>>();
+        let source = app.world.resource_mut::<Assets<Sine>>().add(Sine);
+
+        let mut play = Schedule::default();
+        play.add_systems(play_queued_audio::<Sample, Sine>);
+        let mut clean = Schedule::default();
+        clean.add_systems((stop_all_sinks, clean_stopped_audio_sinks::<Sine>).chain());
+
+        group.throughput(Throughput::Elements(requests as u64));
+        group.bench_function(BenchmarkId::from_parameter(requests), |b| {
+            b.iter_custom(|iters| {
+                let mut elapsed = Duration::ZERO;
+                for _ in 0..iters {
+                    let audio = app.world.resource::<Audio<Sample, Sine>>();
+                    for _ in 0..requests {
+                        let _ = audio.play(source.clone_weak(), sine::Settings::new(0.0, 440.0));
+                    }
+
+                    let start = Instant::now();
+                    play.run(&mut app.world);
+                    elapsed += start.elapsed();
+                    assert_eq!(app.world.resource::<Audio<Sample, Sine>>().queued(), 0);
+
+                    // Stop the sinks and let the mixer drop them, so every frame starts empty.
+                    clean.run(&mut app.world);
+                    oddio::run(&mixer, 44_100, &mut [0.0; 64]);
+                }
+                elapsed
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, enqueue, play_queued);
+criterion_main!(benches);
@@ -0,0 +1,68 @@
+use {
+    bevy::{
+        prelude::{
+            App, Assets, Commands, Deref, Handle, Input, KeyCode, PostStartup, Res, ResMut,
+            Resource, Startup, Update,
+        },
+        DefaultPlugins,
+    },
+    bevy_oddio::{
+        builtins::sine::{self, Sine},
+        fade::FadeCurve,
+        output::AudioSink,
+        Audio, AudioPlugin, PlayOptions,
+    },
+    oddio::Sample,
+};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(AudioPlugin::new())
+        .add_systems(Startup, init_assets)
+        .add_systems(PostStartup, play_sine)
+        .add_systems(Update, get_input)
+        .run();
+}
+
+#[derive(Resource, Deref)]
+struct SineHandle(Handle<Sine>);
+#[derive(Resource)]
+struct SineSink(Handle<AudioSink<Sine>>);
+
+fn init_assets(mut commands: Commands, mut assets: ResMut<Assets<Sine>>) {
+    let handle = assets.add(Sine);
+    commands.insert_resource(SineHandle(handle));
+}
+
+fn play_sine(mut commands: Commands, audio: Res<Audio<Sample, Sine>>, sine: Res<SineHandle>) {
+    // Note is in A4.
+    let handle = audio.play_with_options(
+        sine.clone(),
+        sine::Settings::new(0.0, 440.0),
+        PlayOptions::new().fade_in(2.0, FadeCurve::EqualPower),
+    );
+    commands.insert_resource(SineSink(handle));
+}
+
+fn get_input(
+    keys: Res<Input<KeyCode>>,
+    sink: Res<SineSink>,
+    mut sinks: ResMut<Assets<AudioSink<Sine>>>,
+) {
+    let Some(sink) = sinks.get_mut(&sink.0) else {
+        return;
+    };
+
+    if keys.just_pressed(KeyCode::Space) {
+        if sink.is_paused() {
+            sink.resume_with_fade(0.5, FadeCurve::EqualPower);
+        } else {
+            sink.pause_with_fade(0.5, FadeCurve::EqualPower);
+        }
+    }
+
+    if keys.just_pressed(KeyCode::Return) {
+        sink.stop_with_fade(2.0, FadeCurve::Linear);
+    }
+}
@@ -48,7 +48,7 @@ fn init_assets(mut commands: Commands, mut assets: ResMut<Assets<SineWithGain>>)
 
 fn play_sine_with_gain(
     mut commands: Commands,
-    mut audio: ResMut<Audio<Sample, SineWithGain>>,
+    audio: Res<Audio<Sample, SineWithGain>>,
     sine_with_gain: Res<SineWithGainHandle>,
 ) {
     let handle = audio.play(sine_with_gain.clone(), sine::Settings::new(0.0, 440.0));
@@ -66,6 +66,5 @@ fn change_volume(
 
     let factor = (time.elapsed_seconds_wrapped().sin() + 1.0) / 2.0;
 
-    sink.control::<oddio::Gain<_>, _>()
-        .set_amplitude_ratio(factor);
+    sink.set_volume(factor);
 }
@@ -59,7 +59,7 @@ fn init_assets(mut commands: Commands, mut assets: ResMut<Assets<Noise>>) {
 
 fn play_noise(
     mut commands: Commands,
-    mut audio: ResMut<Audio<[Sample; 2], Noise>>,
+    audio: Res<Audio<[Sample; 2], Noise>>,
     noise: Res<NoiseHandle>,
 ) {
     let handle = audio.play(noise.clone(), ());
@@ -32,11 +32,7 @@ fn init_assets(mut commands: Commands, mut assets: ResMut<Assets<Sine>>) {
     commands.insert_resource(SineHandle(handle));
 }
 
-fn play_sine(
-    mut commands: Commands,
-    mut audio: ResMut<Audio<Sample, Sine>>,
-    noise: Res<SineHandle>,
-) {
+fn play_sine(mut commands: Commands, audio: Res<Audio<Sample, Sine>>, noise: Res<SineHandle>) {
     // Note is in A4.
     let handle = audio.play(noise.clone(), sine::Settings::new(0.0, 440.0));
     commands.insert_resource(SineSink(handle));
@@ -40,7 +40,7 @@ fn init_assets(mut commands: Commands, mut assets: ResMut<Assets<Sine>>) {
     commands.insert_resource(SineHandle(handle));
 }
 
-fn setup(mut commands: Commands, mut audio: ResMut<Audio<Sample, Sine>>, noise: Res<SineHandle>) {
+fn setup(mut commands: Commands, audio: Res<Audio<Sample, Sine>>, noise: Res<SineHandle>) {
     // Note is in A4.
     let handle = audio.play_spatial(
         noise.clone(),
@@ -41,7 +41,7 @@ fn init_assets(mut commands: Commands, mut assets: ResMut<Assets<Sine>>) {
 
 fn setup(
     mut commands: Commands,
-    mut audio: ResMut<Audio<Sample, Sine>>,
+    audio: Res<Audio<Sample, Sine>>,
     noise: Res<SineHandle>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
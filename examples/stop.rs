@@ -34,11 +34,7 @@ fn init_assets(mut commands: Commands, mut assets: ResMut<Assets<Sine>>) {
     commands.insert_resource(SineHandle(handle));
 }
 
-fn play_sine(
-    mut commands: Commands,
-    mut audio: ResMut<Audio<Sample, Sine>>,
-    sine: Res<SineHandle>,
-) {
+fn play_sine(mut commands: Commands, audio: Res<Audio<Sample, Sine>>, sine: Res<SineHandle>) {
     // Note is in A4.
     let handle = audio.play(sine.clone(), sine::Settings::new(0.0, 440.0));
     commands.insert_resource(SineSink(handle));
@@ -53,13 +49,11 @@ fn get_input(
         return;
     };
 
-    let control = sink.control::<oddio::Stop<_>, _>();
-
     if keys.just_pressed(KeyCode::Space) {
-        if control.is_paused() {
-            control.resume();
+        if sink.is_paused() {
+            sink.resume();
         } else {
-            control.pause();
+            sink.pause();
         }
     }
 }
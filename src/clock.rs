@@ -0,0 +1,395 @@
+use {
+    bevy::prelude::{ResMut, Resource},
+    cpal::{OutputStreamTimestamp, StreamInstant},
+    oddio::{Filter, Frame, Seek, Signal},
+    std::{
+        cell::Cell,
+        marker::PhantomData,
+        ops::{Add, Sub},
+        sync::{
+            atomic::{AtomicU32, AtomicU64, Ordering},
+            Arc,
+        },
+    },
+};
+
+/// A point in time on an audio output's clock.
+///
+/// Measured in seconds since the output started rendering.
+/// See [`AudioOutput::now`](crate::output::AudioOutput::now).
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct AudioInstant(f64);
+
+impl AudioInstant {
+    /// Construct an `AudioInstant` from seconds since the output started rendering.
+    #[must_use]
+    pub fn from_secs(seconds: f64) -> Self {
+        Self(seconds)
+    }
+
+    /// Seconds since the output started rendering.
+    #[must_use]
+    pub fn as_secs(self) -> f64 {
+        self.0
+    }
+}
+
+impl Add<f64> for AudioInstant {
+    type Output = AudioInstant;
+
+    fn add(self, seconds: f64) -> Self::Output {
+        AudioInstant(self.0 + seconds)
+    }
+}
+
+impl Sub<f64> for AudioInstant {
+    type Output = AudioInstant;
+
+    fn sub(self, seconds: f64) -> Self::Output {
+        AudioInstant(self.0 - seconds)
+    }
+}
+
+impl Sub for AudioInstant {
+    type Output = f64;
+
+    fn sub(self, other: AudioInstant) -> Self::Output {
+        self.0 - other.0
+    }
+}
+
+/// How far ahead of the output's clock signals are scheduled when they should
+/// start right away, so that they reach the mixer before their start time.
+pub(crate) const SCHEDULE_AHEAD: f64 = 0.1;
+
+/// Frames rendered by an output stream, shared with its audio callback.
+#[derive(Default)]
+pub(crate) struct Clock {
+    frames: AtomicU64,
+    sample_rate: AtomicU32,
+    latency_nanos: AtomicU64,
+    timestamp_nanos: AtomicU64,
+}
+
+impl Clock {
+    pub(crate) fn set_sample_rate(&self, sample_rate: u32) {
+        self.sample_rate.store(sample_rate, Ordering::Relaxed);
+    }
+
+    /// Sample rate of the output stream, or `0` if the output has no stream.
+    #[cfg(any(feature = "flac", feature = "mp3", feature = "ogg", feature = "wav"))]
+    pub(crate) fn sample_rate(&self) -> u32 {
+        self.sample_rate.load(Ordering::Relaxed)
+    }
+
+    /// Called by the audio callback after rendering `frames` frames.
+    pub(crate) fn advance(&self, frames: usize) {
+        self.frames.fetch_add(frames as u64, Ordering::Release);
+    }
+
+    /// Called by the audio callback before rendering, with the instant of the
+    /// stream's first callback as `origin`.
+    #[allow(clippy::cast_possible_truncation)]
+    pub(crate) fn record_timestamp(&self, origin: StreamInstant, timestamp: OutputStreamTimestamp) {
+        let latency = timestamp
+            .playback
+            .duration_since(&timestamp.callback)
+            .unwrap_or_default();
+        let elapsed = timestamp
+            .callback
+            .duration_since(&origin)
+            .unwrap_or_default();
+        self.latency_nanos
+            .store(latency.as_nanos() as u64, Ordering::Relaxed);
+        self.timestamp_nanos
+            .store(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    pub(crate) fn now(&self) -> AudioInstant {
+        let sample_rate = self.sample_rate.load(Ordering::Relaxed);
+        if sample_rate == 0 {
+            return AudioInstant::default();
+        }
+        let frames = self.frames.load(Ordering::Acquire);
+        AudioInstant(frames as f64 / f64::from(sample_rate))
+    }
+}
+
+/// Timing of an audio output's stream, as last reported by its audio callback.
+///
+/// `Output` is the output resource the clock belongs to, e.g.
+/// [`AudioOutput`](crate::output::AudioOutput) (stereo by default) or
+/// [`SpatialAudioOutput`](crate::output::spatial::SpatialAudioOutput).
+/// The clock is refreshed once per frame in `PreUpdate`.
+#[derive(Resource)]
+pub struct AudioClock<Output = crate::output::AudioOutput<[oddio::Sample; 2]>> {
+    clock: Arc<Clock>,
+    frames: u64,
+    sample_rate: u32,
+    latency: f64,
+    timestamp: f64,
+    _marker: PhantomData<fn() -> Output>,
+}
+
+impl<Output> AudioClock<Output> {
+    pub(crate) fn new(clock: Arc<Clock>) -> Self {
+        let mut audio_clock = Self {
+            clock,
+            frames: 0,
+            sample_rate: 0,
+            latency: 0.0,
+            timestamp: 0.0,
+            _marker: PhantomData,
+        };
+        audio_clock.update();
+        audio_clock
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn update(&mut self) {
+        self.frames = self.clock.frames.load(Ordering::Acquire);
+        self.sample_rate = self.clock.sample_rate.load(Ordering::Relaxed);
+        self.latency = self.clock.latency_nanos.load(Ordering::Relaxed) as f64 * 1e-9;
+        self.timestamp = self.clock.timestamp_nanos.load(Ordering::Relaxed) as f64 * 1e-9;
+    }
+
+    /// Number of frames the output stream has rendered.
+    #[must_use]
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Sample rate of the output stream, or `0` if the output has no stream,
+    /// such as one constructed with [`AudioOutput::with_mixer`](crate::output::AudioOutput::with_mixer).
+    #[must_use]
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// The time on the output's clock when this frame began.
+    ///
+    /// This is the time at which the next buffer will start, so signals
+    /// scheduled with [`Audio::play_at`](crate::Audio::play_at) for this instant
+    /// or later are played without being cut short.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn now(&self) -> AudioInstant {
+        if self.sample_rate == 0 {
+            return AudioInstant::default();
+        }
+        AudioInstant(self.frames as f64 / f64::from(self.sample_rate))
+    }
+
+    /// Estimated time in seconds between the audio callback rendering a frame
+    /// and the device playing it.
+    #[must_use]
+    pub fn output_latency(&self) -> f64 {
+        self.latency
+    }
+
+    /// The time on the output's clock that is currently audible, compensated
+    /// for [`output_latency`](Self::output_latency).
+    ///
+    /// Use this to synchronize visuals with what the player hears.
+    #[must_use]
+    pub fn audible_now(&self) -> AudioInstant {
+        self.now() - self.latency
+    }
+
+    /// Seconds between the stream's first audio callback and its latest one,
+    /// as measured by the audio host.
+    ///
+    /// Unlike [`now`](Self::now), this follows the host's clock rather than
+    /// the number of frames rendered, so it keeps advancing across underruns.
+    #[must_use]
+    pub fn timestamp(&self) -> f64 {
+        self.timestamp
+    }
+}
+
+/// System to refresh an [`AudioClock`] from its output's audio callback.
+pub fn update_audio_clock<Output: 'static>(mut audio_clock: ResMut<AudioClock<Output>>) {
+    audio_clock.update();
+}
+
+/// When a scheduled signal starts playing.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) enum Start {
+    #[default]
+    Now,
+    At(AudioInstant),
+    Delayed(f64),
+}
+
+/// Delays a signal until a given time on the output's clock.
+///
+/// The start time is honored to the sample inside the output's mixer.
+///
+/// Every sink in `bevy_oddio` wraps its signal in a `Scheduled`.
+pub struct Scheduled<T: ?Sized> {
+    start: Start,
+    clock: Arc<Clock>,
+    /// Playback position in seconds, relative to the start time.
+    /// `None` until the start time is resolved against the clock.
+    t: Cell<Option<f64>>,
+    inner: T,
+}
+
+impl<T> Scheduled<T> {
+    pub(crate) fn new(signal: T, start: Start, clock: Arc<Clock>) -> Self {
+        Self {
+            start,
+            clock,
+            t: Cell::new(None),
+            inner: signal,
+        }
+    }
+}
+
+impl<T: ?Sized> Scheduled<T> {
+    fn t(&self) -> f64 {
+        if let Some(t) = self.t.get() {
+            return t;
+        }
+        let t = match self.start {
+            Start::Now => 0.0,
+            Start::At(instant) => self.clock.now() - instant,
+            Start::Delayed(seconds) => -seconds,
+        };
+        self.t.set(Some(t));
+        t
+    }
+}
+
+impl<T: Signal + ?Sized> Signal for Scheduled<T>
+where
+    T::Frame: Frame,
+{
+    type Frame = T::Frame;
+
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    fn sample(&self, interval: f32, out: &mut [T::Frame]) {
+        let t = self.t();
+        let dt = f64::from(interval);
+        let silent = if t >= 0.0 {
+            0
+        } else {
+            ((-t / dt).ceil() as usize).min(out.len())
+        };
+
+        let (before, after) = out.split_at_mut(silent);
+        before.fill_with(|| T::Frame::ZERO);
+        if !after.is_empty() {
+            self.inner.sample(interval, after);
+        }
+
+        self.t.set(Some(t + dt * out.len() as f64));
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn remaining(&self) -> f32 {
+        let t = self.t();
+        if t < 0.0 {
+            self.inner.remaining() - t as f32
+        } else {
+            self.inner.remaining()
+        }
+    }
+
+    #[inline]
+    fn handle_dropped(&self) {
+        self.inner.handle_dropped();
+    }
+}
+
+impl<T: Seek + ?Sized> Seek for Scheduled<T>
+where
+    T::Frame: Frame,
+{
+    #[allow(clippy::cast_possible_truncation)]
+    fn seek(&self, seconds: f32) {
+        let t = self.t();
+        let next = t + f64::from(seconds);
+        self.t.set(Some(next));
+        let shift = next.max(0.0) - t.max(0.0);
+        if shift != 0.0 {
+            self.inner.seek(shift as f32);
+        }
+    }
+}
+
+impl<T: ?Sized> Filter for Scheduled<T> {
+    type Inner = T;
+
+    fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use {
+        super::{AudioClock, AudioInstant, Clock, Scheduled, Start},
+        oddio::{Constant, Signal},
+        std::sync::{atomic::Ordering, Arc},
+    };
+
+    #[test]
+    fn audio_clock() {
+        let clock = Arc::new(Clock::default());
+        let mut audio_clock = AudioClock::<()>::new(clock.clone());
+        assert_eq!(audio_clock.now(), AudioInstant::default());
+
+        clock.set_sample_rate(4);
+        clock.advance(6);
+        clock.latency_nanos.store(500_000_000, Ordering::Relaxed);
+        audio_clock.update();
+        assert_eq!(audio_clock.frames(), 6);
+        assert_eq!(audio_clock.now(), AudioInstant::from_secs(1.5));
+        assert_eq!(audio_clock.audible_now(), AudioInstant::from_secs(1.0));
+    }
+
+    #[test]
+    fn delayed() {
+        let scheduled = Scheduled::new(Constant(1.0), Start::Delayed(1.0), Arc::default());
+        let mut buf = [0.0; 6];
+        scheduled.sample(0.25, &mut buf);
+        assert_eq!(buf, [0.0, 0.0, 0.0, 0.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn at_instant() {
+        let clock = Arc::new(Clock::default());
+        clock.set_sample_rate(4);
+        clock.advance(4);
+        let scheduled = Scheduled::new(
+            Constant(1.0),
+            Start::At(AudioInstant::from_secs(1.5)),
+            clock,
+        );
+        let mut buf = [0.0; 4];
+        scheduled.sample(0.25, &mut buf);
+        assert_eq!(buf, [0.0, 0.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn in_the_past() {
+        let clock = Arc::new(Clock::default());
+        clock.set_sample_rate(4);
+        clock.advance(8);
+        let scheduled = Scheduled::new(
+            Constant(1.0),
+            Start::At(AudioInstant::from_secs(1.0)),
+            clock,
+        );
+        let mut buf = [0.0; 2];
+        scheduled.sample(0.25, &mut buf);
+        assert_eq!(buf, [1.0, 1.0]);
+    }
+}
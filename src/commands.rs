@@ -0,0 +1,324 @@
+use {
+    crate::{
+        output::{spatial::SpatialAudioSink, AudioSink},
+        Audio, AudioToPlay, PlayOptions, PlaySource, SpatialSettings, ToSignal,
+    },
+    bevy::{
+        asset::{Asset, HandleId},
+        ecs::system::{Command, Commands, EntityCommands},
+        prelude::{Component, Handle, World},
+    },
+    oddio::{Frame, Sample, Seek, Signal, SpatialOptions},
+    std::marker::PhantomData,
+};
+
+/// Component holding the sink of audio played with [`AudioEntityCommands`].
+///
+/// The component is not removed when the audio stops playing.
+#[derive(Component)]
+pub struct SinkHandle<Sink: Asset>(pub Handle<Sink>);
+
+/// Command that queues audio on the [`Audio<F, Source>`](Audio) resource.
+struct PlayAudio<F, Source: ToSignal + Asset> {
+    audio_to_play: AudioToPlay<Source>,
+    _frame: PhantomData<fn() -> F>,
+}
+
+impl<F, Source: ToSignal + Asset> PlayAudio<F, Source> {
+    fn new(audio_to_play: AudioToPlay<Source>) -> Self {
+        Self {
+            audio_to_play,
+            _frame: PhantomData,
+        }
+    }
+}
+
+impl<F, Source> Command for PlayAudio<F, Source>
+where
+    Source: ToSignal + Asset,
+    F: Frame + 'static,
+{
+    fn apply(self, world: &mut World) {
+        world
+            .resource::<Audio<F, Source>>()
+            .queue
+            .push(self.audio_to_play);
+    }
+}
+
+/// Extension trait for playing audio through [`Commands`], without access to the [`Audio`] resource.
+///
+/// The audio is queued when the commands are applied, and starts playing in
+/// the same frame, as long as the commands are applied before [`PostUpdate`](bevy::prelude::PostUpdate).
+pub trait AudioCommands {
+    /// Play the given type that implements [`Signal`].
+    ///
+    /// See [`Audio::play`].
+    fn play_audio<Source>(
+        &mut self,
+        source_handle: Handle<Source>,
+        settings: Source::Settings,
+    ) -> Handle<AudioSink<Source>>
+    where
+        Source: ToSignal + Asset,
+        <Source::Signal as Signal>::Frame: Frame + 'static,
+    {
+        self.play_audio_with_options(source_handle, settings, PlayOptions::default())
+    }
+
+    /// Play the given type that implements [`Signal`] with the given [`PlayOptions`].
+    ///
+    /// See [`Audio::play_with_options`].
+    fn play_audio_with_options<Source>(
+        &mut self,
+        source_handle: Handle<Source>,
+        settings: Source::Settings,
+        options: PlayOptions,
+    ) -> Handle<AudioSink<Source>>
+    where
+        Source: ToSignal + Asset,
+        <Source::Signal as Signal>::Frame: Frame + 'static;
+
+    /// Play the given type that implements [`Signal`] in the spatial scene.
+    ///
+    /// See [`Audio::play_spatial`].
+    fn play_spatial_audio<Source>(
+        &mut self,
+        source_handle: Handle<Source>,
+        settings: Source::Settings,
+        spatial_options: SpatialOptions,
+    ) -> Handle<SpatialAudioSink<Source>>
+    where
+        Source: ToSignal + Asset,
+        Source::Signal: Signal<Frame = Sample> + Seek,
+    {
+        self.play_spatial_audio_with_options(
+            source_handle,
+            settings,
+            PlayOptions::default(),
+            spatial_options,
+        )
+    }
+
+    /// Play the given type that implements [`Signal`] in the spatial scene
+    /// with the given [`PlayOptions`].
+    ///
+    /// See [`Audio::play_spatial_with_options`].
+    fn play_spatial_audio_with_options<Source>(
+        &mut self,
+        source_handle: Handle<Source>,
+        settings: Source::Settings,
+        options: PlayOptions,
+        spatial_options: SpatialOptions,
+    ) -> Handle<SpatialAudioSink<Source>>
+    where
+        Source: ToSignal + Asset,
+        Source::Signal: Signal<Frame = Sample> + Seek;
+}
+
+impl AudioCommands for Commands<'_, '_> {
+    fn play_audio_with_options<Source>(
+        &mut self,
+        source_handle: Handle<Source>,
+        settings: Source::Settings,
+        options: PlayOptions,
+    ) -> Handle<AudioSink<Source>>
+    where
+        Source: ToSignal + Asset,
+        <Source::Signal as Signal>::Frame: Frame + 'static,
+    {
+        let stop_handle = HandleId::random::<AudioSink<Source>>();
+        self.add(PlayAudio::<<Source::Signal as Signal>::Frame, _>::new(
+            AudioToPlay {
+                source: PlaySource::Asset(source_handle, settings),
+                stop_handle: Some(stop_handle),
+                options,
+                spatial_settings: None,
+            },
+        ));
+        Handle::weak(stop_handle)
+    }
+
+    fn play_spatial_audio_with_options<Source>(
+        &mut self,
+        source_handle: Handle<Source>,
+        settings: Source::Settings,
+        options: PlayOptions,
+        spatial_options: SpatialOptions,
+    ) -> Handle<SpatialAudioSink<Source>>
+    where
+        Source: ToSignal + Asset,
+        Source::Signal: Signal<Frame = Sample> + Seek,
+    {
+        let stop_handle = HandleId::random::<SpatialAudioSink<Source>>();
+        self.add(PlayAudio::<Sample, _>::new(AudioToPlay {
+            source: PlaySource::Asset(source_handle, settings),
+            stop_handle: Some(stop_handle),
+            options,
+            spatial_settings: Some(SpatialSettings {
+                options: spatial_options,
+                buffered_settings: None,
+            }),
+        }));
+        Handle::weak(stop_handle)
+    }
+}
+
+/// Extension trait for playing audio through [`EntityCommands`], attaching
+/// the sink to the entity as a [`SinkHandle`].
+pub trait AudioEntityCommands {
+    /// Play the given type that implements [`Signal`], inserting a
+    /// [`SinkHandle<AudioSink<Source>>`](SinkHandle) on the entity.
+    ///
+    /// See [`AudioCommands::play_audio`].
+    fn play_audio<Source>(
+        &mut self,
+        source_handle: Handle<Source>,
+        settings: Source::Settings,
+    ) -> &mut Self
+    where
+        Source: ToSignal + Asset,
+        <Source::Signal as Signal>::Frame: Frame + 'static,
+    {
+        self.play_audio_with_options(source_handle, settings, PlayOptions::default())
+    }
+
+    /// Play the given type that implements [`Signal`] with the given [`PlayOptions`],
+    /// inserting a [`SinkHandle<AudioSink<Source>>`](SinkHandle) on the entity.
+    ///
+    /// See [`AudioCommands::play_audio_with_options`].
+    fn play_audio_with_options<Source>(
+        &mut self,
+        source_handle: Handle<Source>,
+        settings: Source::Settings,
+        options: PlayOptions,
+    ) -> &mut Self
+    where
+        Source: ToSignal + Asset,
+        <Source::Signal as Signal>::Frame: Frame + 'static;
+
+    /// Play the given type that implements [`Signal`] in the spatial scene,
+    /// inserting a [`SinkHandle<SpatialAudioSink<Source>>`](SinkHandle) on the entity.
+    ///
+    /// See [`AudioCommands::play_spatial_audio`].
+    fn play_spatial_audio<Source>(
+        &mut self,
+        source_handle: Handle<Source>,
+        settings: Source::Settings,
+        spatial_options: SpatialOptions,
+    ) -> &mut Self
+    where
+        Source: ToSignal + Asset,
+        Source::Signal: Signal<Frame = Sample> + Seek,
+    {
+        self.play_spatial_audio_with_options(
+            source_handle,
+            settings,
+            PlayOptions::default(),
+            spatial_options,
+        )
+    }
+
+    /// Play the given type that implements [`Signal`] in the spatial scene with the given
+    /// [`PlayOptions`], inserting a [`SinkHandle<SpatialAudioSink<Source>>`](SinkHandle) on the entity.
+    ///
+    /// See [`AudioCommands::play_spatial_audio_with_options`].
+    fn play_spatial_audio_with_options<Source>(
+        &mut self,
+        source_handle: Handle<Source>,
+        settings: Source::Settings,
+        options: PlayOptions,
+        spatial_options: SpatialOptions,
+    ) -> &mut Self
+    where
+        Source: ToSignal + Asset,
+        Source::Signal: Signal<Frame = Sample> + Seek;
+}
+
+impl AudioEntityCommands for EntityCommands<'_, '_, '_> {
+    fn play_audio_with_options<Source>(
+        &mut self,
+        source_handle: Handle<Source>,
+        settings: Source::Settings,
+        options: PlayOptions,
+    ) -> &mut Self
+    where
+        Source: ToSignal + Asset,
+        <Source::Signal as Signal>::Frame: Frame + 'static,
+    {
+        let sink = self
+            .commands()
+            .play_audio_with_options(source_handle, settings, options);
+        self.insert(SinkHandle(sink))
+    }
+
+    fn play_spatial_audio_with_options<Source>(
+        &mut self,
+        source_handle: Handle<Source>,
+        settings: Source::Settings,
+        options: PlayOptions,
+        spatial_options: SpatialOptions,
+    ) -> &mut Self
+    where
+        Source: ToSignal + Asset,
+        Source::Signal: Signal<Frame = Sample> + Seek,
+    {
+        let sink = self.commands().play_spatial_audio_with_options(
+            source_handle,
+            settings,
+            options,
+            spatial_options,
+        );
+        self.insert(SinkHandle(sink))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{AudioCommands, AudioEntityCommands, SinkHandle},
+        crate::{builtins::sine, output::AudioSink, Audio},
+        bevy::{
+            ecs::system::{CommandQueue, Commands},
+            prelude::{Handle, World},
+        },
+        oddio::Sample,
+    };
+
+    #[test]
+    fn queues_on_apply() {
+        let mut world = World::new();
+        world.init_resource::<Audio<Sample, sine::Sine>>();
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, &world);
+
+        let sink = commands.play_audio(
+            Handle::<sine::Sine>::default(),
+            sine::Settings::new(0.0, 440.0),
+        );
+        let entity = commands
+            .spawn_empty()
+            .play_audio(
+                Handle::<sine::Sine>::default(),
+                sine::Settings::new(0.0, 440.0),
+            )
+            .id();
+        assert_eq!(world.resource::<Audio<Sample, sine::Sine>>().queued(), 0);
+
+        queue.apply(&mut world);
+        let audio = world.resource::<Audio<Sample, sine::Sine>>();
+        let queue: Vec<_> = audio.queue.drain().collect();
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue[0].stop_handle, Some(sink.id()));
+        assert_eq!(
+            queue[1].stop_handle,
+            Some(
+                world
+                    .get::<SinkHandle<AudioSink<sine::Sine>>>(entity)
+                    .unwrap()
+                    .0
+                    .id()
+            )
+        );
+    }
+}
@@ -0,0 +1,36 @@
+use std::ops::Range;
+
+/// Loop points and markers of an [`AudioSource`](crate::AudioSource), as authored in
+/// the file it was loaded from.
+///
+/// WAV files store them in their `smpl` and `cue ` chunks, with the names of the
+/// markers in a `LIST` chunk of type `adtl`. Positions are in frames of the loaded
+/// source, so they stay in place when the file is resampled on load.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cues {
+    /// Frames to repeat when the source is played looping, after playing up to the end of it once.
+    ///
+    /// Looping playback repeats the whole source without one.
+    pub loop_region: Option<Range<usize>>,
+    /// Markers in the order they appear in the source.
+    pub markers: Vec<Marker>,
+}
+
+impl Cues {
+    /// The first marker named `name`.
+    #[must_use]
+    pub fn marker(&self, name: &str) -> Option<&Marker> {
+        self.markers.iter().find(|marker| marker.name == name)
+    }
+}
+
+/// A named position in an [`AudioSource`](crate::AudioSource).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Marker {
+    /// Identifier of the cue point in the file.
+    pub id: u32,
+    /// Label of the marker, or an empty string if it has none.
+    pub name: String,
+    /// Frame the marker is at.
+    pub frame: usize,
+}
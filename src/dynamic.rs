@@ -0,0 +1,56 @@
+use {
+    crate::ToSignal,
+    bevy::reflect::{TypePath, TypeUuid},
+    oddio::{Frame, Sample, Seek, Signal},
+    std::{convert::Infallible, marker::PhantomData},
+};
+
+/// A type-erased signal.
+pub type BoxedSignal<F> = Box<dyn Signal<Frame = F> + Send>;
+
+/// A type-erased signal that can be played in the spatial scene.
+pub type BoxedSpatialSignal = Box<dyn Seek<Frame = Sample> + Send>;
+
+/// Source type for playing any [`BoxedSignal`] without registering a source type of its own.
+///
+/// There are no assets of this type. Signals are played with
+/// [`Audio::play_signal`](crate::Audio::play_signal):
+///
+/// ```ignore
+/// fn play(audio: Res<Audio<[Sample; 2], DynamicSignal<[Sample; 2]>>>) {
+///     let sink = audio.play_signal(Box::new(my_signal));
+/// }
+/// ```
+///
+/// The returned [`AudioSink<DynamicSignal<F>>`](crate::output::AudioSink) can be paused,
+/// stopped, and faded like any other sink.
+#[derive(TypeUuid, TypePath)]
+#[uuid = "3f6b2c8d-1a4e-4d7f-9c0b-5e2a8d4f7b16"]
+pub struct DynamicSignal<F: Frame>(PhantomData<fn() -> F>);
+
+impl<F: Frame> ToSignal for DynamicSignal<F> {
+    type Settings = Infallible;
+    type Signal = BoxedSignal<F>;
+
+    fn to_signal(&self, settings: Self::Settings) -> Self::Signal {
+        match settings {}
+    }
+}
+
+/// Source type for playing any [`BoxedSpatialSignal`] in the spatial scene
+/// without registering a source type of its own.
+///
+/// Signals are played with [`Audio::play_spatial_signal`](crate::Audio::play_spatial_signal).
+/// See [`DynamicSignal`].
+#[derive(TypeUuid, TypePath)]
+#[uuid = "b84e1d5a-7c3f-4a2e-8d6b-0f9c3e7a1d52"]
+pub struct DynamicSpatialSignal;
+
+impl ToSignal for DynamicSpatialSignal {
+    type Settings = Infallible;
+    type Signal = BoxedSpatialSignal;
+
+    fn to_signal(&self, settings: Self::Settings) -> Self::Signal {
+        match settings {}
+    }
+}
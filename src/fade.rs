@@ -0,0 +1,446 @@
+use {
+    oddio::{Controlled, Filter, Frame, Seek, Signal, Swap},
+    std::{
+        cell::Cell,
+        f32::consts::FRAC_PI_2,
+        sync::atomic::{AtomicBool, Ordering},
+    },
+};
+
+/// Shape of the volume envelope during a fade.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FadeCurve {
+    /// Volume changes at a constant rate.
+    #[default]
+    Linear,
+    /// Volume changes slowly at first, then quickly.
+    Quadratic,
+    /// Constant-power fade. Sounds the most natural for most material.
+    EqualPower,
+    /// Volume eases in and out of the fade.
+    SmoothStep,
+}
+
+impl FadeCurve {
+    /// Evaluate the curve at `progress`, which is clamped between `0.0` and `1.0`.
+    #[must_use]
+    pub fn eval(self, progress: f32) -> f32 {
+        let x = progress.clamp(0.0, 1.0);
+        match self {
+            FadeCurve::Linear => x,
+            FadeCurve::Quadratic => x * x,
+            FadeCurve::EqualPower => (x * FRAC_PI_2).sin(),
+            FadeCurve::SmoothStep => x * x * (3.0 - 2.0 * x),
+        }
+    }
+}
+
+/// What happens to a signal when a fade finishes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FadeAction {
+    None,
+    Pause,
+    Stop,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Command {
+    /// Playback position at which the fade starts, or `None` to start it immediately.
+    start: Option<f64>,
+    target: f32,
+    duration: f32,
+    curve: FadeCurve,
+    action: FadeAction,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Envelope {
+    start: f64,
+    duration: f32,
+    from: f32,
+    to: f32,
+    curve: FadeCurve,
+    action: FadeAction,
+}
+
+impl Envelope {
+    fn end(&self) -> f64 {
+        self.start + f64::from(self.duration)
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn level(&self, t: f64) -> f32 {
+        let progress = if self.duration <= 0.0 {
+            1.0
+        } else {
+            ((t - self.start) / f64::from(self.duration)) as f32
+        };
+        if self.to >= self.from {
+            self.from + (self.to - self.from) * self.curve.eval(progress)
+        } else {
+            self.to + (self.from - self.to) * self.curve.eval(1.0 - progress)
+        }
+    }
+}
+
+/// Fades a signal in and out.
+///
+/// The fade is evaluated per sample on the audio thread, and is defined in terms
+/// of the signal's playback time so that it survives [`Seek`]ing.
+///
+/// Every sink in `bevy_oddio` wraps its signal in a `Fade`.
+pub struct Fade<T: ?Sized> {
+    /// Playback position in seconds.
+    t: Cell<f64>,
+    envelope: Cell<Option<Envelope>>,
+    /// Volume of the signal when no fade is active.
+    level: Cell<f32>,
+    paused: AtomicBool,
+    commands: Swap<Option<Command>>,
+    inner: T,
+}
+
+impl<T> Fade<T> {
+    /// Wrap `signal` without fading it in.
+    pub fn new(signal: T) -> Self {
+        Self {
+            t: Cell::new(0.0),
+            envelope: Cell::new(None),
+            level: Cell::new(1.0),
+            paused: AtomicBool::new(false),
+            commands: Swap::new(|| None),
+            inner: signal,
+        }
+    }
+
+    /// Wrap `signal`, fading it in from silence over `duration` seconds.
+    pub fn with_fade_in(signal: T, duration: f32, curve: FadeCurve) -> Self {
+        let fade = Self::new(signal);
+        fade.level.set(0.0);
+        fade.envelope.set(Some(Envelope {
+            start: 0.0,
+            duration,
+            from: 0.0,
+            to: 1.0,
+            curve,
+            action: FadeAction::None,
+        }));
+        fade
+    }
+}
+
+impl<T: ?Sized> Fade<T> {
+    fn current_level(&self, t: f64) -> f32 {
+        match self.envelope.get() {
+            Some(envelope) => envelope.level(t),
+            None => self.level.get(),
+        }
+    }
+
+    fn receive_commands(&self) {
+        if !self.commands.refresh() {
+            return;
+        }
+        // SAFETY: `received` is only accessed by the consumer, which is the audio thread.
+        let Some(command) = (unsafe { (*self.commands.received()).take() }) else {
+            return;
+        };
+        let t = self.t.get();
+        let from = if self.paused.load(Ordering::Relaxed) {
+            0.0
+        } else {
+            self.current_level(t)
+        };
+        // A paused signal fades back in when resumed, and fades out from silence
+        // when stopped so that it still finishes.
+        if command.action != FadeAction::Pause {
+            self.paused.store(false, Ordering::Relaxed);
+        }
+        self.envelope.set(Some(Envelope {
+            start: command.start.unwrap_or(t),
+            duration: command.duration,
+            from,
+            to: command.target,
+            curve: command.curve,
+            action: command.action,
+        }));
+    }
+
+    /// Retire the current envelope if it has finished by `t`.
+    fn finish_envelope(&self, t: f64) {
+        let Some(envelope) = self.envelope.get() else {
+            return;
+        };
+        if t < envelope.end() {
+            return;
+        }
+        self.level.set(envelope.to);
+        match envelope.action {
+            FadeAction::None => self.envelope.set(None),
+            FadeAction::Pause => {
+                self.envelope.set(None);
+                self.paused.store(true, Ordering::Relaxed);
+            }
+            // Keep the envelope so that `remaining` reports the signal as finished.
+            FadeAction::Stop => {}
+        }
+    }
+}
+
+impl<T: Signal + ?Sized> Signal for Fade<T>
+where
+    T::Frame: Frame,
+{
+    type Frame = T::Frame;
+
+    #[allow(clippy::float_cmp)]
+    fn sample(&self, interval: f32, out: &mut [T::Frame]) {
+        self.receive_commands();
+
+        if self.paused.load(Ordering::Relaxed) {
+            out.fill_with(|| T::Frame::ZERO);
+            return;
+        }
+
+        self.inner.sample(interval, out);
+
+        let t0 = self.t.get();
+        let dt = f64::from(interval);
+
+        if self.envelope.get().is_some() || self.level.get() != 1.0 {
+            for (i, frame) in out.iter_mut().enumerate() {
+                #[allow(clippy::cast_precision_loss)]
+                let level = self.current_level(t0 + dt * i as f64);
+                for sample in frame.channels_mut() {
+                    *sample *= level;
+                }
+            }
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        let t = t0 + dt * out.len() as f64;
+        self.t.set(t);
+        self.finish_envelope(t);
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn remaining(&self) -> f32 {
+        if self.paused.load(Ordering::Relaxed) {
+            return f32::INFINITY;
+        }
+        match self.envelope.get() {
+            Some(envelope) if envelope.action == FadeAction::Stop => self
+                .inner
+                .remaining()
+                .min((envelope.end() - self.t.get()) as f32),
+            _ => self.inner.remaining(),
+        }
+    }
+
+    #[inline]
+    fn handle_dropped(&self) {
+        self.inner.handle_dropped();
+    }
+}
+
+impl<T: Seek + ?Sized> Seek for Fade<T>
+where
+    T::Frame: Frame,
+{
+    fn seek(&self, seconds: f32) {
+        if self.paused.load(Ordering::Relaxed) {
+            return;
+        }
+        self.t.set(self.t.get() + f64::from(seconds));
+        self.inner.seek(seconds);
+    }
+}
+
+impl<T: ?Sized> Filter for Fade<T> {
+    type Inner = T;
+
+    fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+/// Thread-safe control for a [`Fade`] filter.
+pub struct FadeControl<'a> {
+    commands: &'a Swap<Option<Command>>,
+    paused: &'a AtomicBool,
+}
+
+// SAFETY: `FadeControl` only touches the producer side of the `Swap` and an atomic.
+unsafe impl<'a, T: 'a> Controlled<'a> for Fade<T> {
+    type Control = FadeControl<'a>;
+
+    unsafe fn make_control(signal: &'a Fade<T>) -> Self::Control {
+        FadeControl {
+            commands: &signal.commands,
+            paused: &signal.paused,
+        }
+    }
+}
+
+impl FadeControl<'_> {
+    fn send(&mut self, command: Command) {
+        // SAFETY: `pending` is only accessed by the producer, which is this control.
+        unsafe {
+            *self.commands.pending() = Some(command);
+        }
+        self.commands.flush();
+    }
+
+    /// Fade the signal to full volume over `duration` seconds.
+    ///
+    /// Resumes the signal if it was paused with [`pause_with_fade`](Self::pause_with_fade).
+    pub fn fade_in(&mut self, duration: f32, curve: FadeCurve) {
+        self.send(Command {
+            start: None,
+            target: 1.0,
+            duration,
+            curve,
+            action: FadeAction::None,
+        });
+    }
+
+    /// Fade the signal to silence over `duration` seconds, then pause it.
+    pub fn pause_with_fade(&mut self, duration: f32, curve: FadeCurve) {
+        self.send(Command {
+            start: None,
+            target: 0.0,
+            duration,
+            curve,
+            action: FadeAction::Pause,
+        });
+    }
+
+    /// Fade the signal to silence over `duration` seconds, then stop it for good.
+    pub fn stop_with_fade(&mut self, duration: f32, curve: FadeCurve) {
+        self.send(Command {
+            start: None,
+            target: 0.0,
+            duration,
+            curve,
+            action: FadeAction::Stop,
+        });
+    }
+
+    /// Fade the signal to silence over `duration` seconds once its playback position
+    /// reaches `position` seconds, then stop it for good.
+    ///
+    /// Unlike [`stop_with_fade`](Self::stop_with_fade), the fade starts on the exact sample,
+    /// which makes it suitable for crossfading into a signal scheduled with
+    /// [`PlayOptions::start_at`](crate::PlayOptions::start_at).
+    pub fn stop_with_fade_at(&mut self, position: f64, duration: f32, curve: FadeCurve) {
+        self.send(Command {
+            start: Some(position),
+            target: 0.0,
+            duration,
+            curve,
+            action: FadeAction::Stop,
+        });
+    }
+
+    /// Immediately resume a signal paused with [`pause_with_fade`](Self::pause_with_fade).
+    pub fn resume(&mut self) {
+        self.fade_in(0.0, FadeCurve::Linear);
+    }
+
+    /// Whether the signal has been paused by a fade.
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use {
+        super::{Fade, FadeControl, FadeCurve},
+        oddio::{Constant, Controlled, Signal},
+    };
+
+    fn control<T>(fade: &Fade<T>) -> FadeControl<'_> {
+        // SAFETY: only one control exists at a time in these tests.
+        unsafe { Fade::make_control(fade) }
+    }
+
+    #[test]
+    fn fade_in() {
+        let fade = Fade::with_fade_in(Constant(1.0), 1.0, FadeCurve::Linear);
+        let mut buf = [0.0; 6];
+        fade.sample(0.25, &mut buf);
+        assert_eq!(buf, [0.0, 0.25, 0.5, 0.75, 1.0, 1.0]);
+        fade.sample(0.25, &mut buf);
+        assert_eq!(buf, [1.0; 6]);
+    }
+
+    #[test]
+    fn stop_with_fade() {
+        let fade = Fade::new(Constant(1.0));
+        control(&fade).stop_with_fade(1.0, FadeCurve::Linear);
+        let mut buf = [0.0; 2];
+        fade.sample(0.25, &mut buf);
+        assert_eq!(buf, [1.0, 0.75]);
+        assert!((fade.remaining() - 0.5).abs() < f32::EPSILON);
+        fade.sample(0.25, &mut buf);
+        assert_eq!(buf, [0.5, 0.25]);
+        assert!(fade.remaining() <= 0.0);
+    }
+
+    #[test]
+    fn stop_with_fade_at() {
+        let fade = Fade::new(Constant(1.0));
+        control(&fade).stop_with_fade_at(0.5, 0.5, FadeCurve::Linear);
+        let mut buf = [0.0; 4];
+        fade.sample(0.25, &mut buf);
+        assert_eq!(buf, [1.0, 1.0, 1.0, 0.5]);
+        assert!(fade.remaining() <= 0.0);
+    }
+
+    #[test]
+    fn pause_with_fade() {
+        let fade = Fade::new(Constant(1.0));
+        control(&fade).pause_with_fade(0.5, FadeCurve::Linear);
+        let mut buf = [0.0; 2];
+        fade.sample(0.25, &mut buf);
+        assert_eq!(buf, [1.0, 0.5]);
+        assert!(control(&fade).is_paused());
+        assert!(fade.remaining().is_infinite());
+        fade.sample(0.25, &mut buf);
+        assert_eq!(buf, [0.0, 0.0]);
+
+        control(&fade).resume();
+        fade.sample(0.25, &mut buf);
+        assert_eq!(buf, [1.0, 1.0]);
+        assert!(!control(&fade).is_paused());
+    }
+
+    #[test]
+    fn stop_after_pause() {
+        let fade = Fade::new(Constant(1.0));
+        control(&fade).pause_with_fade(0.25, FadeCurve::Linear);
+        let mut buf = [0.0; 2];
+        fade.sample(0.25, &mut buf);
+        assert!(control(&fade).is_paused());
+
+        control(&fade).stop_with_fade(0.5, FadeCurve::Linear);
+        fade.sample(0.25, &mut buf);
+        assert_eq!(buf, [0.0, 0.0]);
+        assert!(!control(&fade).is_paused());
+        assert!(fade.remaining() <= 0.0);
+    }
+
+    #[test]
+    fn equal_power_is_symmetric() {
+        for i in 0..=10 {
+            #[allow(clippy::cast_precision_loss)]
+            let x = i as f32 / 10.0;
+            let fade_in = FadeCurve::EqualPower.eval(x);
+            let fade_out = FadeCurve::EqualPower.eval(1.0 - x);
+            assert!((fade_in * fade_in + fade_out * fade_out - 1.0).abs() < 1e-6);
+        }
+    }
+}
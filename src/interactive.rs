@@ -0,0 +1,680 @@
+use {
+    crate::{
+        clock::{AudioClock, AudioInstant, SCHEDULE_AHEAD},
+        fade::{Fade, FadeCurve},
+        music::MusicSource,
+        output::AudioSink,
+        Audio, AudioSourceSettings, PlayOptions, ToSignal,
+    },
+    bevy::{
+        prelude::{Assets, Event, EventWriter, Handle, Res, ResMut, Resource},
+        utils::HashMap,
+    },
+    oddio::Sample,
+};
+
+/// Tempo and meter of a piece of music.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TempoMap {
+    /// Beats per minute.
+    pub bpm: f64,
+    /// Beats per bar, i.e. the upper number of the time signature.
+    pub beats_per_bar: u32,
+    /// Seconds from the start of the audio to the first downbeat.
+    pub offset: f64,
+}
+
+impl TempoMap {
+    /// Construct a `TempoMap` whose first downbeat is at the start of the audio.
+    #[must_use]
+    pub fn new(bpm: f64, beats_per_bar: u32) -> Self {
+        Self {
+            bpm,
+            beats_per_bar,
+            offset: 0.0,
+        }
+    }
+
+    /// Place the first downbeat `offset` seconds into the audio.
+    #[must_use]
+    pub fn with_offset(mut self, offset: f64) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Duration of a beat in seconds.
+    #[must_use]
+    pub fn beat_duration(&self) -> f64 {
+        60.0 / self.bpm
+    }
+
+    /// Duration of a bar in seconds.
+    #[must_use]
+    pub fn bar_duration(&self) -> f64 {
+        self.beat_duration() * f64::from(self.beats_per_bar)
+    }
+
+    /// Position in seconds of the first beat at or after `position`.
+    #[must_use]
+    pub fn next_beat(&self, position: f64) -> f64 {
+        self.next_multiple(position, self.beat_duration())
+    }
+
+    /// Position in seconds of the first downbeat at or after `position`.
+    #[must_use]
+    pub fn next_bar(&self, position: f64) -> f64 {
+        self.next_multiple(position, self.bar_duration())
+    }
+
+    fn next_multiple(&self, position: f64, period: f64) -> f64 {
+        self.offset + ((position - self.offset) / period).ceil().max(0.0) * period
+    }
+}
+
+/// A section of interactive music, such as an intro, a loop, or an ending.
+#[derive(Debug, Clone)]
+pub struct MusicSection {
+    /// The audio of the section.
+    pub source: Handle<MusicSource>,
+    /// Tempo and meter of the section, used to quantize transitions.
+    pub tempo: TempoMap,
+    /// Whether the section loops until the music transitions away from it.
+    pub looping: bool,
+}
+
+impl MusicSection {
+    /// Construct a looping `MusicSection`.
+    #[must_use]
+    pub fn new(source: Handle<MusicSource>, tempo: TempoMap) -> Self {
+        Self {
+            source,
+            tempo,
+            looping: true,
+        }
+    }
+
+    /// Set whether the section loops.
+    #[must_use]
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+}
+
+/// Where in the current section a transition happens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Quantize {
+    /// As soon as possible.
+    Immediate,
+    /// On the next beat.
+    Beat,
+    /// On the next downbeat.
+    #[default]
+    Bar,
+    /// At the end of the current section.
+    End,
+}
+
+/// How [`InteractiveMusic`] moves from one section to another.
+#[derive(Debug, Clone, Default)]
+pub struct Transition {
+    quantize: Quantize,
+    fade: Option<(f32, FadeCurve)>,
+    stinger: Option<Handle<MusicSource>>,
+}
+
+impl Transition {
+    /// Construct a `Transition` that cuts to the next section at the given boundary.
+    #[must_use]
+    pub fn at(quantize: Quantize) -> Self {
+        Self {
+            quantize,
+            ..Self::default()
+        }
+    }
+
+    /// Crossfade between the sections over `duration` seconds, starting at the boundary.
+    #[must_use]
+    pub fn with_fade(mut self, duration: f32, curve: FadeCurve) -> Self {
+        self.fade = Some((duration, curve));
+        self
+    }
+
+    /// Play `stinger` on top of the music at the boundary.
+    #[must_use]
+    pub fn with_stinger(mut self, stinger: Handle<MusicSource>) -> Self {
+        self.stinger = Some(stinger);
+        self
+    }
+}
+
+/// Sent when [`InteractiveMusic`] reaches a transition boundary and
+/// the audible section changes.
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct SectionChanged {
+    /// The section that was playing, if any.
+    pub previous: Option<String>,
+    /// The section that is now playing, or `None` if the music stopped.
+    pub current: Option<String>,
+}
+
+/// A single play of a section.
+struct Voice {
+    section: String,
+    sink: Handle<AudioSink<MusicSource>>,
+    start: AudioInstant,
+    /// Length of the section's audio in seconds.
+    length: f64,
+    /// When the voice stops, which is earlier than its natural end if it was transitioned away from.
+    end: AudioInstant,
+    /// Fade out to send to the sink once it exists, as a position in the voice and a fade.
+    /// Voices cut before they start have no position or fade, and are stopped outright.
+    stop_at: Option<(f64, f32, FadeCurve)>,
+    /// Whether the voice was transitioned away from, so that it must not loop.
+    stopping: bool,
+    /// Whether the sink may still be waiting in the play queue.
+    queued: bool,
+}
+
+/// Music made of sections that are switched on beat and bar boundaries.
+///
+/// Transitions are quantized against the stereo [`AudioOutput`](crate::output::AudioOutput)'s
+/// clock, so they land on the exact sample of the boundary regardless of the frame rate.
+///
+/// Playback is driven by the [`update_interactive_music`] system. Transitions wait
+/// until the audio of the section is loaded.
+#[derive(Resource, Default)]
+pub struct InteractiveMusic {
+    sections: HashMap<String, MusicSection>,
+    requests: Vec<(Option<String>, Transition)>,
+    voices: Vec<Voice>,
+    current: Option<String>,
+    /// Section changes that have been scheduled but not reached yet.
+    changes: Vec<(AudioInstant, Option<String>)>,
+}
+
+impl InteractiveMusic {
+    /// Construct `InteractiveMusic` without any sections.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a section that can be transitioned to by `name`.
+    pub fn add_section(&mut self, name: impl Into<String>, section: MusicSection) {
+        self.sections.insert(name.into(), section);
+    }
+
+    /// The section with the given name.
+    #[must_use]
+    pub fn section(&self, name: &str) -> Option<&MusicSection> {
+        self.sections.get(name)
+    }
+
+    /// Transition to the section `name`.
+    ///
+    /// If nothing is playing, the section starts right away.
+    pub fn transition_to(&mut self, name: impl Into<String>, transition: Transition) {
+        self.requests.push((Some(name.into()), transition));
+    }
+
+    /// Stop the music at the given boundary.
+    pub fn stop(&mut self, transition: Transition) {
+        self.requests.push((None, transition));
+    }
+
+    /// The section currently audible, if any.
+    #[must_use]
+    pub fn current(&self) -> Option<&str> {
+        self.current.as_deref()
+    }
+
+    /// The voice audible at `instant`, which is the last one started by then.
+    fn voice_at(&self, instant: AudioInstant) -> Option<&Voice> {
+        self.voices
+            .iter()
+            .filter(|voice| voice.start <= instant && instant < voice.end)
+            .max_by(|a, b| a.start.as_secs().total_cmp(&b.start.as_secs()))
+    }
+
+    /// The instant of the first boundary of `quantize` at or after `instant`.
+    fn boundary(&self, instant: AudioInstant, quantize: Quantize) -> AudioInstant {
+        let Some(voice) = self.voice_at(instant) else {
+            return instant;
+        };
+        let Some(section) = self.sections.get(&voice.section) else {
+            return instant;
+        };
+        let tempo = &section.tempo;
+        let length = voice.length;
+        let position = instant - voice.start;
+        let boundary = match quantize {
+            Quantize::Immediate => position,
+            Quantize::Beat => tempo.next_beat(position),
+            Quantize::Bar => tempo.next_bar(position),
+            Quantize::End => length,
+        };
+        let end = voice.start + length;
+        if boundary < length || quantize == Quantize::End {
+            voice.start + boundary
+        } else if section.looping && !voice.stopping {
+            // The boundary falls in the next loop of the section.
+            let first = match quantize {
+                Quantize::Beat => tempo.next_beat(0.0),
+                _ => tempo.next_bar(0.0),
+            };
+            end + first
+        } else {
+            end
+        }
+    }
+}
+
+/// System that drives [`InteractiveMusic`].
+#[allow(clippy::needless_pass_by_value, clippy::too_many_lines)]
+pub fn update_interactive_music(
+    mut music: ResMut<InteractiveMusic>,
+    audio: Res<Audio<[Sample; 2], MusicSource>>,
+    sources: Res<Assets<MusicSource>>,
+    mut sinks: ResMut<Assets<AudioSink<MusicSource>>>,
+    clock: Res<AudioClock>,
+    mut section_changed: EventWriter<SectionChanged>,
+) {
+    let music = &mut *music;
+    let now = clock.now();
+    let ahead = now + SCHEDULE_AHEAD;
+
+    let runtime = |music: &InteractiveMusic, name: &str| -> Option<f64> {
+        let section = music.sections.get(name)?;
+        Some(sources.get(&section.source)?.frames.runtime())
+    };
+
+    let requests = std::mem::take(&mut music.requests);
+    for (i, (name, transition)) in requests.iter().enumerate() {
+        let length = match name {
+            Some(name) => match runtime(music, name) {
+                Some(length) => Some(length),
+                None if music.sections.contains_key(name) => {
+                    // Wait for the section to load.
+                    music.requests.extend_from_slice(&requests[i..]);
+                    break;
+                }
+                None => continue,
+            },
+            None => None,
+        };
+
+        let boundary = music.boundary(ahead, transition.quantize);
+        let (fade, curve) = transition.fade.unwrap_or((0.0, FadeCurve::Linear));
+
+        for voice in &mut music.voices {
+            if voice.end <= boundary {
+                continue;
+            }
+            voice.stopping = true;
+            if voice.start >= boundary {
+                // Scheduled loops that have not started yet are never heard.
+                voice.stop_at = Some((0.0, 0.0, curve));
+                voice.end = voice.start;
+            } else {
+                let position = boundary - voice.start;
+                voice.stop_at = Some((position, fade, curve));
+                voice.end = boundary;
+            }
+        }
+
+        if let (Some(name), Some(length)) = (name, length) {
+            let mut options = PlayOptions::new().start_at(boundary);
+            if fade > 0.0 {
+                options = options.fade_in(fade, curve);
+            }
+            let sink = audio.play_with_options(
+                music.sections[name].source.clone_weak(),
+                AudioSourceSettings::default(),
+                options,
+            );
+            music.voices.push(Voice {
+                section: name.clone(),
+                sink,
+                start: boundary,
+                length,
+                end: boundary + length,
+                stop_at: None,
+                stopping: false,
+                queued: true,
+            });
+        }
+
+        if let Some(stinger) = &transition.stinger {
+            audio.play_with_options(
+                stinger.clone_weak(),
+                AudioSourceSettings::default(),
+                PlayOptions::new().start_at(boundary),
+            );
+        }
+
+        music.changes.retain(|(instant, _)| *instant < boundary);
+        music.changes.push((boundary, name.clone()));
+    }
+
+    // Loop the newest voice once it has started, so the next loop is in the mixer
+    // well before it has to be heard.
+    let newest = music
+        .voices
+        .iter()
+        .filter(|voice| !voice.stopping)
+        .max_by(|a, b| a.start.as_secs().total_cmp(&b.start.as_secs()));
+    if let Some(voice) = newest {
+        let looping = music
+            .sections
+            .get(&voice.section)
+            .is_some_and(|section| section.looping);
+        if looping && voice.start <= now {
+            let name = voice.section.clone();
+            let start = voice.end;
+            if let Some(length) = runtime(music, &name) {
+                let sink = audio.play_with_options(
+                    music.sections[&name].source.clone_weak(),
+                    AudioSourceSettings::default(),
+                    PlayOptions::new().start_at(start),
+                );
+                music.voices.push(Voice {
+                    section: name,
+                    sink,
+                    start,
+                    length,
+                    end: start + length,
+                    stop_at: None,
+                    stopping: false,
+                    queued: true,
+                });
+            }
+        }
+    }
+
+    while let Some(index) = music
+        .changes
+        .iter()
+        .position(|(instant, _)| *instant <= now)
+    {
+        let (_, current) = music.changes.remove(index);
+        let previous = std::mem::replace(&mut music.current, current.clone());
+        if previous != current {
+            section_changed.send(SectionChanged { previous, current });
+        }
+    }
+    // A section that does not loop ends on its own.
+    if music.changes.is_empty() && music.current.is_some() && music.voice_at(now).is_none() {
+        section_changed.send(SectionChanged {
+            previous: music.current.take(),
+            current: None,
+        });
+    }
+
+    // Sinks only exist once the queued audio has been played, after this system,
+    // so fades are retried until they can be sent. A sink that is still missing
+    // a frame later was removed, or its source failed to load.
+    music.voices.retain_mut(|voice| {
+        let Some(sink) = sinks.get_mut(&voice.sink) else {
+            return std::mem::take(&mut voice.queued)
+                && (voice.stop_at.is_some() || voice.end > now);
+        };
+        match voice.stop_at.take() {
+            Some((position, duration, _)) if position <= 0.0 && duration <= 0.0 => sink.stop(),
+            Some((position, duration, curve)) => sink
+                .control::<Fade<<MusicSource as ToSignal>::Signal>, _>()
+                .stop_with_fade_at(position, duration, curve),
+            None => {}
+        }
+        voice.end > now && !sink.is_stopped()
+    });
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use {
+        super::{
+            update_interactive_music, InteractiveMusic, MusicSection, Quantize, SectionChanged,
+            TempoMap, Transition, Voice,
+        },
+        crate::{
+            clock::{update_audio_clock, AudioClock, AudioInstant, Clock},
+            fade::FadeCurve,
+            music::MusicSource,
+            output::{
+                clean_stopped_audio_sinks, play_queued_audio, AudioOutput, AudioSink, AudioSinks,
+            },
+            Audio, AudioSource,
+        },
+        bevy::{
+            asset::{AddAsset, AssetPlugin, Assets, Handle},
+            core::TaskPoolPlugin,
+            ecs::event::Events,
+            prelude::{App, IntoSystemConfigs, Update},
+        },
+        oddio::{Frames, Mixer, Sample},
+        std::sync::Arc,
+    };
+
+    /// Frames per second of the clock and of every section.
+    const RATE: u32 = 10;
+
+    fn app(clock: &Arc<Clock>) -> App {
+        clock.set_sample_rate(RATE);
+        let (mixer, _signal) = oddio::split(Mixer::<[Sample; 2]>::new());
+        let mut app = App::new();
+        app.add_plugins((TaskPoolPlugin::default(), AssetPlugin::default()))
+            .add_asset::<MusicSource>()
+            .add_asset::<AudioSink<MusicSource>>()
+            .add_event::<SectionChanged>()
+            .insert_resource(AudioOutput::with_mixer(mixer))
+            .insert_resource(AudioClock::<AudioOutput<[Sample; 2]>>::new(clock.clone()))
+            .init_resource::<Audio<[Sample; 2], MusicSource>>()
+            .init_resource::<AudioSinks<MusicSource>>()
+            .init_resource::<InteractiveMusic>()
+            .add_systems(
+                Update,
+                (
+                    update_audio_clock::<AudioOutput<[Sample; 2]>>,
+                    update_interactive_music,
+                    play_queued_audio::<[Sample; 2], MusicSource>,
+                    clean_stopped_audio_sinks::<MusicSource>,
+                )
+                    .chain(),
+            );
+        app
+    }
+
+    /// Add a section of `seconds` at 120 beats per minute in 4/4, so bars last two seconds.
+    fn add_section(app: &mut App, name: &str, seconds: usize, looping: bool) {
+        let frames = vec![[0.0; 2]; seconds * RATE as usize];
+        let source = app
+            .world
+            .resource_mut::<Assets<MusicSource>>()
+            .add(AudioSource::new(Frames::from_slice(RATE, &frames)));
+        app.world.resource_mut::<InteractiveMusic>().add_section(
+            name,
+            MusicSection::new(source, TempoMap::new(120.0, 4)).with_looping(looping),
+        );
+    }
+
+    /// The section, start, and end of every voice, in seconds.
+    fn voices(app: &App) -> Vec<(&str, f64, f64)> {
+        app.world
+            .resource::<InteractiveMusic>()
+            .voices
+            .iter()
+            .map(|voice| {
+                (
+                    voice.section.as_str(),
+                    voice.start.as_secs(),
+                    voice.end.as_secs(),
+                )
+            })
+            .collect()
+    }
+
+    fn changes(app: &mut App) -> Vec<(Option<String>, Option<String>)> {
+        app.world
+            .resource_mut::<Events<SectionChanged>>()
+            .drain()
+            .map(|change| (change.previous, change.current))
+            .collect()
+    }
+
+    fn change(previous: Option<&str>, current: Option<&str>) -> (Option<String>, Option<String>) {
+        (previous.map(str::to_owned), current.map(str::to_owned))
+    }
+
+    #[test]
+    fn quantize() {
+        // Two beats per second, four beats per bar.
+        let tempo = TempoMap::new(120.0, 4);
+        assert_eq!(tempo.next_beat(0.0), 0.0);
+        assert_eq!(tempo.next_beat(0.1), 0.5);
+        assert_eq!(tempo.next_bar(0.1), 2.0);
+        assert_eq!(tempo.next_bar(2.0), 2.0);
+
+        let tempo = tempo.with_offset(0.25);
+        assert_eq!(tempo.next_bar(0.0), 0.25);
+        assert_eq!(tempo.next_beat(1.0), 1.25);
+    }
+
+    #[test]
+    fn boundaries() {
+        let mut music = InteractiveMusic::new();
+        let tempo = TempoMap::new(120.0, 4).with_offset(0.25);
+        music.add_section("loop", MusicSection::new(Handle::default(), tempo));
+        music.add_section(
+            "once",
+            MusicSection::new(Handle::default(), tempo).with_looping(false),
+        );
+        let voice = |section: &str, start: f64| Voice {
+            section: section.to_owned(),
+            sink: Handle::default(),
+            start: AudioInstant::from_secs(start),
+            length: 5.0,
+            end: AudioInstant::from_secs(start + 5.0),
+            stop_at: None,
+            stopping: false,
+            queued: false,
+        };
+        let boundary = |music: &InteractiveMusic, instant: f64, quantize| {
+            music
+                .boundary(AudioInstant::from_secs(instant), quantize)
+                .as_secs()
+        };
+
+        // Nothing is playing, so every transition happens right away.
+        assert_eq!(boundary(&music, 1.3, Quantize::Bar), 1.3);
+
+        music.voices.push(voice("loop", 1.0));
+        assert_eq!(boundary(&music, 1.3, Quantize::Immediate), 1.3);
+        assert_eq!(boundary(&music, 1.3, Quantize::Beat), 1.75);
+        assert_eq!(boundary(&music, 1.3, Quantize::Bar), 3.25);
+        assert_eq!(boundary(&music, 3.25, Quantize::Bar), 3.25);
+        assert_eq!(boundary(&music, 1.3, Quantize::End), 6.0);
+        // Past the last beat and bar, the boundary falls in the next loop.
+        assert_eq!(boundary(&music, 5.9, Quantize::Beat), 6.25);
+        assert_eq!(boundary(&music, 5.5, Quantize::Bar), 6.25);
+
+        // A section that does not loop, or is being transitioned away from, ends instead.
+        music.voices[0].stopping = true;
+        assert_eq!(boundary(&music, 5.5, Quantize::Bar), 6.0);
+        music.voices[0] = voice("once", 1.0);
+        assert_eq!(boundary(&music, 5.9, Quantize::Beat), 6.0);
+    }
+
+    #[test]
+    fn crossfades_on_bar() {
+        let clock = Arc::new(Clock::default());
+        let mut app = app(&clock);
+        add_section(&mut app, "calm", 4, true);
+        add_section(&mut app, "combat", 4, true);
+        let stinger = app.world.resource::<InteractiveMusic>().sections["combat"]
+            .source
+            .clone_weak();
+
+        let mut music = app.world.resource_mut::<InteractiveMusic>();
+        music.transition_to("calm", Transition::at(Quantize::Bar));
+        app.update();
+        // Nothing was playing, so the section starts as soon as it can be scheduled.
+        assert_eq!(voices(&app), [("calm", 0.1, 4.1)]);
+
+        clock.advance(2);
+        app.update();
+        // Once it has started, the next loop is scheduled at its end.
+        assert_eq!(voices(&app), [("calm", 0.1, 4.1), ("calm", 4.1, 8.1)]);
+        assert_eq!(changes(&mut app), [change(None, Some("calm"))]);
+
+        let mut music = app.world.resource_mut::<InteractiveMusic>();
+        music.transition_to(
+            "combat",
+            Transition::at(Quantize::Bar)
+                .with_fade(1.0, FadeCurve::Linear)
+                .with_stinger(stinger),
+        );
+        app.update();
+        // The playing voice fades out from the next bar, and the next loop is cut
+        // before it is ever heard.
+        assert_eq!(voices(&app), [("calm", 0.1, 2.1), ("combat", 2.1, 6.1)]);
+        let sinks = app.world.resource::<Assets<AudioSink<MusicSource>>>();
+        assert_eq!(
+            sinks.len(),
+            3,
+            "the cut loop is cleaned up, and the stinger plays"
+        );
+        assert!(app.world.resource::<InteractiveMusic>().voices[0]
+            .stop_at
+            .is_none());
+        assert!(changes(&mut app).is_empty());
+
+        clock.advance(20);
+        app.update();
+        assert_eq!(changes(&mut app), [change(Some("calm"), Some("combat"))]);
+        assert_eq!(voices(&app), [("combat", 2.1, 6.1), ("combat", 6.1, 10.1)]);
+    }
+
+    #[test]
+    fn ends_sections_that_do_not_loop() {
+        let clock = Arc::new(Clock::default());
+        let mut app = app(&clock);
+        add_section(&mut app, "outro", 1, false);
+
+        let mut music = app.world.resource_mut::<InteractiveMusic>();
+        music.transition_to("outro", Transition::at(Quantize::Immediate));
+        for frames in [0, 2, 10] {
+            clock.advance(frames);
+            app.update();
+        }
+        assert_eq!(
+            changes(&mut app),
+            [change(None, Some("outro")), change(Some("outro"), None)]
+        );
+        assert!(voices(&app).is_empty());
+    }
+
+    #[test]
+    fn drops_voices_without_sinks() {
+        let clock = Arc::new(Clock::default());
+        let mut app = app(&clock);
+        add_section(&mut app, "calm", 4, true);
+
+        let mut music = app.world.resource_mut::<InteractiveMusic>();
+        music.transition_to("calm", Transition::at(Quantize::Immediate));
+        app.update();
+        // Remove the sink, as if it was cleaned up, before its stop is sent.
+        let sink = app.world.resource::<InteractiveMusic>().voices[0].sink.id();
+        let removed = app
+            .world
+            .resource_mut::<Assets<AudioSink<MusicSource>>>()
+            .remove(sink);
+        assert!(removed.is_some());
+
+        let mut music = app.world.resource_mut::<InteractiveMusic>();
+        music.stop(Transition::at(Quantize::Bar).with_fade(1.0, FadeCurve::Linear));
+        app.update();
+        assert!(voices(&app).is_empty());
+    }
+}
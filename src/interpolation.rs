@@ -0,0 +1,416 @@
+use {
+    oddio::{Controlled, Frame, Frames, Sample, Seek, Signal},
+    std::{
+        cell::Cell,
+        f64::consts::PI,
+        ops::Range,
+        sync::{
+            atomic::{AtomicIsize, Ordering},
+            Arc, OnceLock,
+        },
+    },
+};
+
+/// Zero crossings of the sinc kernel on either side of the interpolated frame.
+const ZERO_CROSSINGS: usize = 8;
+/// Kernel values stored per zero crossing. The kernel is linearly interpolated between them.
+const RESOLUTION: usize = 512;
+/// Fastest speed that is still filtered before the frames are read.
+///
+/// Playing faster than this aliases, but keeps the kernel from growing without bound.
+const MAX_FILTERED_SPEED: f64 = 16.0;
+
+/// How frames between the samples of an [`AudioSource`](crate::AudioSource) are computed
+/// when it is played at a sample rate or speed other than its own.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Straight lines between neighbouring samples.
+    ///
+    /// The cheapest mode, and what [`oddio::FramesSignal`] does. Pitching up aliases audibly.
+    #[default]
+    Linear,
+    /// Catmull-Rom splines through the four nearest samples.
+    ///
+    /// Smoother than [`Linear`](Interpolation::Linear) for a little more work, but still aliases
+    /// when pitching up.
+    Cubic,
+    /// A windowed sinc filter, which follows the playback speed to remove anything
+    /// above the Nyquist frequency instead of letting it alias.
+    ///
+    /// The most expensive mode, best kept for sounds that are pitched up a lot, such as engines.
+    Sinc,
+}
+
+impl Interpolation {
+    /// Interpolate a frame at position `s`, in samples, advancing `step` samples per frame.
+    fn interpolate<F: Frame + Copy>(self, frames: Looped<'_, F>, s: f64, step: f64) -> F {
+        match self {
+            Interpolation::Linear => linear(frames, s),
+            Interpolation::Cubic => cubic(frames, s),
+            Interpolation::Sinc => sinc(frames, s, step),
+        }
+    }
+}
+
+/// Frames read as if their loop region repeated forever.
+#[derive(Clone, Copy)]
+struct Looped<'a, F> {
+    frames: &'a [F],
+    loop_region: Option<(isize, isize)>,
+}
+
+impl<F: Frame + Copy> Looped<'_, F> {
+    /// The frame at `index`, or silence outside of the frames.
+    fn get(self, index: isize) -> F {
+        let index = match self.loop_region {
+            Some((start, end)) if index >= end => start + (index - start) % (end - start),
+            _ => index,
+        };
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| self.frames.get(index))
+            .copied()
+            .unwrap_or(F::ZERO)
+    }
+}
+
+/// Add `frame` scaled by `weight` to `out`.
+fn accumulate<F: Frame>(out: &mut F, frame: &F, weight: Sample) {
+    for (out, sample) in out.channels_mut().iter_mut().zip(frame.channels()) {
+        *out += sample * weight;
+    }
+}
+
+/// The same as [`Frames::interpolate`].
+#[allow(clippy::cast_possible_truncation)]
+fn linear<F: Frame + Copy>(frames: Looped<'_, F>, s: f64) -> F {
+    let base = s.trunc() as isize;
+    let t = s.fract() as f32;
+    let (a, b) = (frames.get(base), frames.get(base + 1));
+    let mut out = a;
+    for (out, (a, b)) in out
+        .channels_mut()
+        .iter_mut()
+        .zip(a.channels().iter().zip(b.channels()))
+    {
+        *out = a + t * (b - a);
+    }
+    out
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn cubic<F: Frame + Copy>(frames: Looped<'_, F>, s: f64) -> F {
+    let base = s.floor();
+    let t = (s - base) as f32;
+    let base = base as isize;
+    let (t2, t3) = (t * t, t * t * t);
+    let weights = [
+        0.5 * (-t3 + 2.0 * t2 - t),
+        0.5 * (3.0 * t3 - 5.0 * t2 + 2.0),
+        0.5 * (-3.0 * t3 + 4.0 * t2 + t),
+        0.5 * (t3 - t2),
+    ];
+
+    let mut out = F::ZERO;
+    for (offset, weight) in (-1..).zip(weights) {
+        accumulate(&mut out, &frames.get(base + offset), weight);
+    }
+    out
+}
+
+/// Blackman-windowed sinc, sampled [`RESOLUTION`] times per zero crossing.
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+fn sinc_table() -> &'static [f32] {
+    static TABLE: OnceLock<Box<[f32]>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        (0..=ZERO_CROSSINGS * RESOLUTION)
+            .map(|i| {
+                let x = i as f64 / RESOLUTION as f64;
+                let sinc = if i == 0 {
+                    1.0
+                } else {
+                    (PI * x).sin() / (PI * x)
+                };
+                let position = x / ZERO_CROSSINGS as f64;
+                let window =
+                    0.42 + 0.5 * (PI * position).cos() + 0.08 * (2.0 * PI * position).cos();
+                (sinc * window) as f32
+            })
+            .collect()
+    })
+}
+
+/// The kernel at `x` zero crossings from its center.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+fn kernel(table: &[f32], x: f64) -> f32 {
+    let position = x.abs() * RESOLUTION as f64;
+    let index = position as usize;
+    if index + 1 >= table.len() {
+        return 0.0;
+    }
+    let fract = (position - index as f64) as f32;
+    table[index] + (table[index + 1] - table[index]) * fract
+}
+
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+fn sinc<F: Frame + Copy>(frames: Looped<'_, F>, s: f64, step: f64) -> F {
+    let table = sinc_table();
+    // Lower the cutoff when frames are skipped, so they can't alias.
+    let cutoff = 1.0 / step.abs().clamp(1.0, MAX_FILTERED_SPEED);
+    let half = (ZERO_CROSSINGS as f64 / cutoff).ceil() as isize;
+    let base = s.floor();
+    let fract = s - base;
+    let base = base as isize;
+
+    let mut out = F::ZERO;
+    let mut total = 0.0;
+    for offset in 1 - half..=half {
+        let weight = kernel(table, (offset as f64 - fract) * cutoff);
+        total += weight;
+        accumulate(&mut out, &frames.get(base + offset), weight);
+    }
+    // Keep the gain at DC exactly one.
+    if total != 0.0 {
+        for sample in out.channels_mut() {
+            *sample /= total;
+        }
+    }
+    out
+}
+
+/// An audio signal backed by a static sequence of samples, played back with the
+/// [`Interpolation`] of choice.
+///
+/// Otherwise the same as [`oddio::FramesSignal`].
+pub struct InterpolatedFrames<F> {
+    data: Arc<Frames<F>>,
+    interpolation: Interpolation,
+    /// Playback position in seconds.
+    t: Cell<f64>,
+    /// Playback position in samples, for [`InterpolatedFramesControl`].
+    sample_t: AtomicIsize,
+    /// Frames to repeat once playback reaches the end of them.
+    loop_region: Option<Range<usize>>,
+}
+
+impl<F> InterpolatedFrames<F> {
+    /// Create an audio signal from some samples.
+    ///
+    /// `start_seconds` adjusts the initial playback position, and may be negative.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn new(data: Arc<Frames<F>>, start_seconds: f64, interpolation: Interpolation) -> Self {
+        if interpolation == Interpolation::Sinc {
+            // Build the table now, instead of on the audio thread.
+            sinc_table();
+        }
+        Self {
+            sample_t: AtomicIsize::new((start_seconds * f64::from(data.rate())) as isize),
+            t: Cell::new(start_seconds),
+            interpolation,
+            data,
+            loop_region: None,
+        }
+    }
+
+    /// Keep repeating `region`, in frames, once playback reaches its end.
+    ///
+    /// The region is clamped to the frames. Empty regions loop nothing, and play the frames once.
+    #[must_use]
+    pub fn with_loop(mut self, region: Range<usize>) -> Self {
+        let end = region.end.min(self.data.len());
+        self.loop_region = (region.start < end).then_some(region.start..end);
+        self
+    }
+
+    /// Frames repeated once playback reaches the end of them, if any.
+    #[must_use]
+    pub fn loop_region(&self) -> Option<Range<usize>> {
+        self.loop_region.clone()
+    }
+
+    /// Wrap position `s`, in samples, back into the loop region once it is past its end.
+    #[allow(clippy::cast_precision_loss)]
+    fn wrap(&self, s: f64) -> f64 {
+        match &self.loop_region {
+            Some(region) if s >= region.end as f64 => {
+                let start = region.start as f64;
+                start + (s - start) % (region.end - region.start) as f64
+            }
+            _ => s,
+        }
+    }
+
+    #[allow(clippy::cast_possible_wrap)]
+    fn looped(&self) -> Looped<'_, F> {
+        Looped {
+            frames: &self.data,
+            loop_region: self
+                .loop_region
+                .as_ref()
+                .map(|region| (region.start as isize, region.end as isize)),
+        }
+    }
+
+    /// The interpolation used to play the frames.
+    #[must_use]
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+}
+
+impl<F: Frame + Copy> Signal for InterpolatedFrames<F> {
+    type Frame = F;
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    fn sample(&self, interval: f32, out: &mut [F]) {
+        let rate = f64::from(self.data.rate());
+        let s0 = self.t.get() * rate;
+        let ds = f64::from(interval) * rate;
+        let frames = self.looped();
+        for (i, o) in out.iter_mut().enumerate() {
+            *o = self
+                .interpolation
+                .interpolate(frames, self.wrap(s0 + ds * i as f64), ds);
+        }
+        let mut t = self.t.get() + f64::from(interval) * out.len() as f64;
+        if self.loop_region.is_some() {
+            t = self.wrap(t * rate) / rate;
+        }
+        self.t.set(t);
+        self.sample_t.store((t * rate) as isize, Ordering::Relaxed);
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    fn remaining(&self) -> f32 {
+        if self.loop_region.is_some() {
+            return f32::INFINITY;
+        }
+        (self.data.len() as f64 / f64::from(self.data.rate()) - self.t.get()) as f32
+    }
+}
+
+impl<F: Frame + Copy> Seek for InterpolatedFrames<F> {
+    fn seek(&self, seconds: f32) {
+        self.t.set(self.t.get() + f64::from(seconds));
+    }
+}
+
+/// Thread-safe control for an [`InterpolatedFrames`], giving access to the current playback position.
+pub struct InterpolatedFramesControl<'a>(&'a AtomicIsize, u32);
+
+// SAFETY: The control only reads `sample_t`, which is atomic.
+unsafe impl<'a, F: 'a> Controlled<'a> for InterpolatedFrames<F> {
+    type Control = InterpolatedFramesControl<'a>;
+
+    unsafe fn make_control(signal: &'a InterpolatedFrames<F>) -> Self::Control {
+        InterpolatedFramesControl(&signal.sample_t, signal.data.rate())
+    }
+}
+
+impl InterpolatedFramesControl<'_> {
+    /// Get the current playback position in seconds.
+    ///
+    /// This may be negative if the signal started at a negative time,
+    /// and may be past the end of the frames.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn playback_position(&self) -> f64 {
+        self.0.load(Ordering::Relaxed) as f64 / f64::from(self.1)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use {
+        super::{InterpolatedFrames, Interpolation},
+        oddio::{Frames, FramesSignal, Signal},
+        std::f32::consts::TAU,
+    };
+
+    #[allow(clippy::cast_precision_loss)]
+    fn sine(frequency: f32, rate: u32, frames: usize) -> std::sync::Arc<Frames<f32>> {
+        Frames::from_iter(
+            rate,
+            (0..frames).map(|i| (TAU * frequency * i as f32 / rate as f32).sin()),
+        )
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        #[allow(clippy::cast_precision_loss)]
+        let mean = samples.iter().map(|sample| sample * sample).sum::<f32>() / samples.len() as f32;
+        mean.sqrt()
+    }
+
+    #[test]
+    fn linear_matches_frames_signal() {
+        let frames = sine(440.0, 44100, 4410);
+        let expected = FramesSignal::new(frames.clone(), 0.01);
+        let actual = InterpolatedFrames::new(frames, 0.01, Interpolation::Linear);
+        let (mut a, mut b) = ([0.0; 256], [0.0; 256]);
+        expected.sample(1.0 / 48000.0, &mut a);
+        actual.sample(1.0 / 48000.0, &mut b);
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn passes_through_samples() {
+        let frames = Frames::from_slice(4, &[0.0, 1.0, -0.5, 0.25, 0.75, 0.0]);
+        for interpolation in [Interpolation::Cubic, Interpolation::Sinc] {
+            let signal = InterpolatedFrames::new(frames.clone(), 0.0, interpolation);
+            let mut out = [0.0; 6];
+            signal.sample(0.25, &mut out);
+            for (out, expected) in out.iter().zip(frames.iter()) {
+                assert!(
+                    (out - expected).abs() < 1e-3,
+                    "{interpolation:?}: {out} != {expected}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn loops_region() {
+        let frames = Frames::from_slice(1, &[1.0, 2.0, 3.0, 4.0, 5.0]);
+        let signal = InterpolatedFrames::new(frames, 0.0, Interpolation::Linear).with_loop(1..4);
+        let mut out = [0.0; 10];
+        signal.sample(1.0, &mut out);
+        assert_eq!(out, [1.0, 2.0, 3.0, 4.0, 2.0, 3.0, 4.0, 2.0, 3.0, 4.0]);
+        assert_eq!(signal.remaining(), f32::INFINITY);
+
+        // Halfway between the end of the region and its start.
+        let signal = InterpolatedFrames::new(
+            Frames::from_slice(1, &[0.0, 1.0, 2.0]),
+            0.0,
+            Interpolation::Linear,
+        )
+        .with_loop(0..3);
+        let mut out = [0.0; 6];
+        signal.sample(0.5, &mut out);
+        assert_eq!(out, [0.0, 0.5, 1.0, 1.5, 2.0, 1.0]);
+    }
+
+    #[test]
+    fn sinc_filters_when_pitched_up() {
+        // 20 kHz played twice as fast would be 40 kHz, above the 24 kHz Nyquist frequency.
+        let frames = sine(20000.0, 48000, 48000);
+        let play = |interpolation| {
+            let signal = InterpolatedFrames::new(frames.clone(), 0.0, interpolation);
+            let mut out = vec![0.0; 20000];
+            signal.sample(2.0 / 48000.0, &mut out);
+            rms(&out[100..])
+        };
+        // Linear interpolation folds it back down to 8 kHz.
+        assert!(play(Interpolation::Linear) > 0.5);
+        assert!(play(Interpolation::Sinc) < 0.01);
+    }
+}
@@ -0,0 +1,225 @@
+use {
+    crate::ToSignal,
+    bevy::reflect::{TypePath, TypeUuid},
+    oddio::{Frame, Gain, Seek, Signal},
+    std::cell::Cell,
+};
+
+/// Number of frames mixed at once by [`Layered`].
+const CHUNK: usize = 256;
+
+/// A layer of a [`LayeredSound`].
+pub struct Layer<Source: ToSignal> {
+    /// The source played by this layer.
+    pub source: Source,
+    /// The settings the source's signal is created with.
+    pub settings: Source::Settings,
+    /// Volume of the layer, as an amplitude ratio.
+    pub volume: f32,
+    /// Seconds after the sound starts before this layer starts.
+    pub delay: f32,
+}
+
+impl<Source: ToSignal> Layer<Source> {
+    /// Construct a `Layer` that starts immediately at full volume.
+    pub fn new(source: Source, settings: Source::Settings) -> Self {
+        Self {
+            source,
+            settings,
+            volume: 1.0,
+            delay: 0.0,
+        }
+    }
+
+    /// Play the layer at the given volume, as an amplitude ratio.
+    #[must_use]
+    pub fn with_volume(mut self, volume: f32) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    /// Start the layer `delay` seconds after the sound starts.
+    #[must_use]
+    pub fn with_delay(mut self, delay: f32) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+/// A sound made of several layers started in sync, such as the mechanical,
+/// body, and tail of a gunshot.
+///
+/// All layers are mixed into a single signal, so they share one sink and are
+/// paused, faded, and stopped together.
+#[derive(TypeUuid, TypePath)]
+#[uuid = "c41d7a2e-95b3-4f08-8e6a-2b7f0c9d1e3a"]
+pub struct LayeredSound<Source: ToSignal> {
+    layers: Vec<Layer<Source>>,
+}
+
+impl<Source: ToSignal> LayeredSound<Source> {
+    /// Construct a `LayeredSound` from the given layers.
+    pub fn new(layers: impl IntoIterator<Item = Layer<Source>>) -> Self {
+        Self {
+            layers: layers.into_iter().collect(),
+        }
+    }
+
+    /// The layers of this sound.
+    #[must_use]
+    pub fn layers(&self) -> &[Layer<Source>] {
+        &self.layers
+    }
+}
+
+impl<Source> ToSignal for LayeredSound<Source>
+where
+    Source: ToSignal,
+    Source::Settings: Clone,
+    <Source::Signal as Signal>::Frame: Frame + Copy,
+{
+    type Settings = ();
+    type Signal = Gain<Layered<Source::Signal>>;
+
+    fn to_signal(&self, _settings: Self::Settings) -> Self::Signal {
+        Gain::new(Layered::new(self.layers.iter().map(|layer| {
+            (
+                layer.source.to_signal(layer.settings.clone()),
+                layer.volume,
+                layer.delay,
+            )
+        })))
+    }
+}
+
+struct LayerSignal<T> {
+    signal: T,
+    volume: f32,
+    delay: f64,
+}
+
+/// Mixes several signals, each starting after its own delay and scaled by its own volume.
+pub struct Layered<T> {
+    /// Playback position in seconds.
+    t: Cell<f64>,
+    layers: Vec<LayerSignal<T>>,
+}
+
+impl<T> Layered<T> {
+    /// Mix the given signals, each along with its volume and delay in seconds.
+    pub fn new(layers: impl IntoIterator<Item = (T, f32, f32)>) -> Self {
+        Self {
+            t: Cell::new(0.0),
+            layers: layers
+                .into_iter()
+                .map(|(signal, volume, delay)| LayerSignal {
+                    signal,
+                    volume,
+                    delay: f64::from(delay.max(0.0)),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<T: Signal> Signal for Layered<T>
+where
+    T::Frame: Frame + Copy,
+{
+    type Frame = T::Frame;
+
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    fn sample(&self, interval: f32, out: &mut [T::Frame]) {
+        out.fill(T::Frame::ZERO);
+        let t = self.t.get();
+        let dt = f64::from(interval);
+        let mut buffer = [T::Frame::ZERO; CHUNK];
+
+        for layer in &self.layers {
+            // Frames of `out` before this layer starts.
+            let silent = if t >= layer.delay {
+                0
+            } else {
+                (((layer.delay - t) / dt).ceil() as usize).min(out.len())
+            };
+            for chunk in out[silent..].chunks_mut(CHUNK) {
+                let buffer = &mut buffer[..chunk.len()];
+                layer.signal.sample(interval, buffer);
+                for (out, frame) in chunk.iter_mut().zip(buffer.iter()) {
+                    for (out, sample) in out.channels_mut().iter_mut().zip(frame.channels()) {
+                        *out += sample * layer.volume;
+                    }
+                }
+            }
+        }
+
+        self.t.set(t + dt * out.len() as f64);
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn remaining(&self) -> f32 {
+        let t = self.t.get();
+        self.layers
+            .iter()
+            .map(|layer| (layer.delay - t).max(0.0) as f32 + layer.signal.remaining())
+            .fold(f32::NEG_INFINITY, f32::max)
+    }
+
+    fn handle_dropped(&self) {
+        for layer in &self.layers {
+            layer.signal.handle_dropped();
+        }
+    }
+}
+
+impl<T: Seek> Seek for Layered<T>
+where
+    T::Frame: Frame + Copy,
+{
+    #[allow(clippy::cast_possible_truncation)]
+    fn seek(&self, seconds: f32) {
+        let t = self.t.get();
+        let next = t + f64::from(seconds);
+        for layer in &self.layers {
+            let shift = (next - layer.delay).max(0.0) - (t - layer.delay).max(0.0);
+            if shift != 0.0 {
+                layer.signal.seek(shift as f32);
+            }
+        }
+        self.t.set(next);
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use {
+        super::Layered,
+        oddio::{Constant, Frames, FramesSignal, Signal},
+    };
+
+    #[test]
+    fn delayed_layers() {
+        let layered = Layered::new([(Constant(1.0), 1.0, 0.0), (Constant(1.0), 0.5, 0.5)]);
+        let mut buf = [0.0; 4];
+        layered.sample(0.25, &mut buf);
+        assert_eq!(buf, [1.0, 1.0, 1.5, 1.5]);
+    }
+
+    #[test]
+    fn remaining_includes_delay() {
+        let frames = Frames::from_slice(4, &[1.0; 4]);
+        let layered = Layered::new([
+            (FramesSignal::new(frames.clone(), 0.0), 1.0, 0.0),
+            (FramesSignal::new(frames, 0.0), 1.0, 0.5),
+        ]);
+        assert_eq!(layered.remaining(), 1.5);
+        let mut buf = [0.0; 2];
+        layered.sample(0.25, &mut buf);
+        assert_eq!(layered.remaining(), 1.0);
+    }
+}
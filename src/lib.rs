@@ -11,57 +11,186 @@
 
 use {
     bevy::{
-        asset::{Asset, HandleId},
-        prelude::{AddAsset, App, Handle as BevyHandle, Plugin, Resource},
+        asset::{Asset, AssetServer, HandleId, LoadState},
+        ecs::system::SystemParam,
+        prelude::{AddAsset, App, Assets, Handle as BevyHandle, Plugin, Res, Resource},
         reflect::TypeUuid,
     },
+    clock::{update_audio_clock, AudioClock, AudioInstant, Clock, Scheduled, Start},
     cpal::SupportedStreamConfigRange,
-    oddio::{Frame, Frames, FramesSignal, Gain, Sample, Seek, Signal, SpatialOptions, Speed},
+    cues::Cues,
+    dynamic::{DynamicSignal, DynamicSpatialSignal},
+    fade::{Fade, FadeCurve},
+    interactive::{update_interactive_music, InteractiveMusic, SectionChanged},
+    interpolation::{InterpolatedFrames, Interpolation},
+    layered::LayeredSound,
+    music::{update_music_player, MusicPlayer, TrackChanged},
+    oddio::{Frame, Frames, Gain, Sample, Seek, Signal, SpatialOptions, Speed},
     output::{
-        play_queued_audio,
+        clean_stopped_audio_sinks, play_queued_audio,
         spatial::{
+            clean_stopped_spatial_audio_sinks, clean_stopped_spatial_buffered_audio_sinks,
             play_queued_spatial_audio, play_queued_spatial_buffered_audio, SpatialAudioOutput,
             SpatialAudioSink, SpatialAudioSinks, SpatialBufferedAudioSink,
             SpatialBufferedAudioSinks,
         },
         AudioOutput, AudioSink, AudioSinks,
     },
-    parking_lot::RwLock,
+    queue::PlayQueue,
+    random::RandomSound,
     std::{
-        collections::VecDeque,
         marker::PhantomData,
         sync::{Arc, Mutex},
     },
+    stems::StemMusic,
 };
 pub use {cpal, oddio};
 
 /// [`oddio`] builtin types that can be directly used in [`Audio::play`].
 pub mod builtins;
+/// Audio device time and scheduled playback.
+pub mod clock;
+/// Playing audio through [`Commands`](bevy::prelude::Commands).
+pub mod commands;
+/// Loop points and markers of audio sources.
+pub mod cues;
+/// Playing signals without registering a source type.
+pub mod dynamic;
+/// Fading signals in and out.
+pub mod fade;
 /// Newtypes for working around [bevyengine/bevy#5432](https://github.com/bevyengine/bevy/issues/5432)
 pub mod frames;
-
-use bevy::{prelude::PostUpdate, reflect::TypePath};
+/// Music that transitions between sections on beat and bar boundaries.
+pub mod interactive;
+/// Interpolating audio sources played at other rates and speeds.
+pub mod interpolation;
+/// Sounds made of several layers played together.
+pub mod layered;
+/// Details and tags of loaded audio files.
+#[cfg(any(feature = "flac", feature = "mp3", feature = "ogg", feature = "wav"))]
+pub mod metadata;
+/// Playlist-based music playback.
+pub mod music;
+/// Sound containers that play a random variation every time.
+pub mod random;
+/// Sounds defined in data files.
+#[cfg(feature = "sound_event")]
+pub mod sound_event;
+/// Music split into stems that are faded in and out by a parameter.
+pub mod stems;
+/// Audio decoded while it plays.
+#[cfg(any(feature = "flac", feature = "mp3", feature = "ogg", feature = "wav"))]
+pub mod stream;
+
+use bevy::{
+    prelude::{IntoSystemConfigs, PostUpdate, PreUpdate},
+    reflect::TypePath,
+};
 pub use frames::*;
 
-mod loader;
+/// Errors of the audio file loaders.
+pub mod loader;
 /// Audio output
 pub mod output;
+mod queue;
 
 struct AudioToPlay<Source>
 where
     Source: ToSignal + Asset,
 {
-    source_handle: BevyHandle<Source>,
-    stop_handle: HandleId,
-    settings: Source::Settings,
+    source: PlaySource<Source>,
+    /// `None` for one-shot sounds, which have no sink.
+    stop_handle: Option<HandleId>,
+    options: PlayOptions,
     spatial_settings: Option<SpatialSettings>,
 }
 
+/// What an [`AudioToPlay`] plays.
+enum PlaySource<Source>
+where
+    Source: ToSignal + Asset,
+{
+    /// A source asset, whose signal is created once it is loaded.
+    Asset(BevyHandle<Source>, Source::Settings),
+    /// A signal created ahead of time.
+    Signal(Source::Signal),
+}
+
+impl<Source> PlaySource<Source>
+where
+    Source: ToSignal + Asset,
+{
+    /// Create the signal to play, or give back `self` if the source is not loaded yet.
+    fn into_signal(
+        self,
+        sources: &Assets<Source>,
+        context: &SignalContext,
+    ) -> Result<Source::Signal, Self> {
+        match self {
+            PlaySource::Asset(handle, settings) => match sources.get(&handle) {
+                Some(source) if source.is_loaded(context) => {
+                    Ok(source.to_signal_with_context(settings, context))
+                }
+                _ => Err(PlaySource::Asset(handle, settings)),
+            },
+            PlaySource::Signal(signal) => Ok(signal),
+        }
+    }
+}
+
+/// Options for starting playback that apply to every [`ToSignal`] source.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlayOptions {
+    fade_in: Option<(f32, FadeCurve)>,
+    start: Start,
+}
+
+impl PlayOptions {
+    /// Construct default `PlayOptions`, which start the signal immediately at full volume.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fade the signal in from silence over `duration` seconds.
+    #[must_use]
+    pub fn fade_in(mut self, duration: f32, curve: FadeCurve) -> Self {
+        self.fade_in = Some((duration, curve));
+        self
+    }
+
+    /// Start the signal at the given instant on the output's clock.
+    ///
+    /// If the instant has already passed, the signal starts immediately.
+    #[must_use]
+    pub fn start_at(mut self, instant: AudioInstant) -> Self {
+        self.start = Start::At(instant);
+        self
+    }
+
+    /// Start the signal `seconds` after it reaches the output.
+    #[must_use]
+    pub fn delay(mut self, seconds: f64) -> Self {
+        self.start = Start::Delayed(seconds);
+        self
+    }
+
+    fn wrap<S>(self, signal: S, clock: Arc<Clock>) -> Scheduled<Fade<S>> {
+        let fade = match self.fade_in {
+            Some((duration, curve)) => Fade::with_fade_in(signal, duration, curve),
+            None => Fade::new(signal),
+        };
+        Scheduled::new(fade, self.start, clock)
+    }
+}
+
+#[derive(Clone, Copy)]
 struct SpatialSettings {
     options: SpatialOptions,
     buffered_settings: Option<BufferedSettings>,
 }
 
+#[derive(Clone, Copy)]
 struct BufferedSettings {
     max_distance: f32,
     rate: u32,
@@ -69,13 +198,16 @@ struct BufferedSettings {
 }
 
 /// Resource that can play any type that implements [`Signal`].
+///
+/// Play requests are pushed onto a lock-free queue, so any number of systems
+/// can play audio in parallel through `Res<Audio<F, Source>>`.
 #[derive(Resource)]
 pub struct Audio<F, Source = AudioSource<F>>
 where
     Source: ToSignal + Asset,
     F: Frame,
 {
-    queue: RwLock<VecDeque<AudioToPlay<Source>>>,
+    queue: PlayQueue<AudioToPlay<Source>>,
     _frame: PhantomData<fn() -> F>,
 }
 
@@ -88,20 +220,118 @@ where
     ///
     /// Returns a handle that can be paused or permanently stopped.
     pub fn play(
-        &mut self,
+        &self,
+        source_handle: BevyHandle<Source>,
+        settings: Source::Settings,
+    ) -> BevyHandle<AudioSink<Source>> {
+        self.play_with_options(source_handle, settings, PlayOptions::default())
+    }
+
+    /// Play the given type that implements [`Signal`] with the given [`PlayOptions`].
+    ///
+    /// Returns a handle that can be paused or permanently stopped.
+    pub fn play_with_options(
+        &self,
         source_handle: BevyHandle<Source>,
         settings: Source::Settings,
+        options: PlayOptions,
     ) -> BevyHandle<AudioSink<Source>> {
         let stop_handle = HandleId::random::<AudioSink<Source>>();
         let audio_to_play = AudioToPlay {
+            source: PlaySource::Asset(source_handle, settings),
+            stop_handle: Some(stop_handle),
+            options,
+            spatial_settings: None,
+        };
+        self.queue.push(audio_to_play);
+        BevyHandle::<AudioSink<Source>>::weak(stop_handle)
+    }
+
+    /// Play the given type that implements [`Signal`] at the given instant
+    /// on the output's clock.
+    ///
+    /// The start time is honored to the sample. Use [`AudioOutput::now`] to get the current time.
+    ///
+    /// Returns a handle that can be paused or permanently stopped.
+    pub fn play_at(
+        &self,
+        source_handle: BevyHandle<Source>,
+        settings: Source::Settings,
+        instant: AudioInstant,
+    ) -> BevyHandle<AudioSink<Source>> {
+        self.play_with_options(
+            source_handle,
+            settings,
+            PlayOptions::default().start_at(instant),
+        )
+    }
+
+    /// Play the given type that implements [`Signal`] `seconds` after it reaches the output.
+    ///
+    /// Returns a handle that can be paused or permanently stopped.
+    pub fn play_delayed(
+        &self,
+        source_handle: BevyHandle<Source>,
+        settings: Source::Settings,
+        seconds: f64,
+    ) -> BevyHandle<AudioSink<Source>> {
+        self.play_with_options(
             source_handle,
-            stop_handle,
             settings,
+            PlayOptions::default().delay(seconds),
+        )
+    }
+
+    /// Play the given type that implements [`Signal`], without a sink to control it.
+    ///
+    /// Useful for short sounds like UI clicks or impacts. Nothing is kept around
+    /// after the signal finishes playing.
+    pub fn play_oneshot(&self, source_handle: BevyHandle<Source>, settings: Source::Settings) {
+        let audio_to_play = AudioToPlay {
+            source: PlaySource::Asset(source_handle, settings),
+            stop_handle: None,
+            options: PlayOptions::default(),
             spatial_settings: None,
         };
-        self.queue.write().push_back(audio_to_play);
+        self.queue.push(audio_to_play);
+    }
+
+    /// Play a signal that has already been created.
+    ///
+    /// Use [`DynamicSignal<F>`](dynamic::DynamicSignal) as the source to play any boxed
+    /// signal, such as a procedural one-off, without registering a source type.
+    ///
+    /// Returns a handle that can be paused or permanently stopped.
+    pub fn play_signal(&self, signal: Source::Signal) -> BevyHandle<AudioSink<Source>> {
+        self.play_signal_with_options(signal, PlayOptions::default())
+    }
+
+    /// Play a signal that has already been created with the given [`PlayOptions`].
+    ///
+    /// See [`Audio::play_signal`].
+    pub fn play_signal_with_options(
+        &self,
+        signal: Source::Signal,
+        options: PlayOptions,
+    ) -> BevyHandle<AudioSink<Source>> {
+        let stop_handle = HandleId::random::<AudioSink<Source>>();
+        let audio_to_play = AudioToPlay {
+            source: PlaySource::Signal(signal),
+            stop_handle: Some(stop_handle),
+            options,
+            spatial_settings: None,
+        };
+        self.queue.push(audio_to_play);
         BevyHandle::<AudioSink<Source>>::weak(stop_handle)
     }
+
+    /// Number of requests waiting to be played.
+    ///
+    /// Requests stay queued until their source is loaded.
+    #[must_use]
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
 }
 
 impl<F, Source> Default for Audio<F, Source>
@@ -111,7 +341,7 @@ where
 {
     fn default() -> Self {
         Self {
-            queue: RwLock::default(),
+            queue: PlayQueue::default(),
             _frame: PhantomData,
         }
     }
@@ -125,6 +355,26 @@ where
 pub struct AudioSource<F: Frame> {
     /// Raw audio data. See [`Frames`].
     pub frames: Arc<Frames<F>>,
+    /// Loop points and markers of the audio data.
+    pub cues: Cues,
+}
+
+impl<F: Frame> AudioSource<F> {
+    /// Construct an `AudioSource` from frames, without loop points or markers.
+    #[must_use]
+    pub fn new(frames: Arc<Frames<F>>) -> Self {
+        Self {
+            frames,
+            cues: Cues::default(),
+        }
+    }
+
+    /// Set the loop points and markers of the source.
+    #[must_use]
+    pub fn with_cues(mut self, cues: Cues) -> Self {
+        self.cues = cues;
+        self
+    }
 }
 
 /// Trait for a type that generates a signal.
@@ -140,26 +390,159 @@ pub trait ToSignal {
     /// Create a new [`Signal`](oddio::Signal)
     /// based on the implementing type.
     fn to_signal(&self, settings: Self::Settings) -> Self::Signal;
+
+    /// Whether the assets this source depends on are loaded.
+    ///
+    /// Requests to play the source are deferred until this returns `true`.
+    /// Only sources that reference other assets need to override this.
+    fn is_loaded(&self, _context: &SignalContext) -> bool {
+        true
+    }
+
+    /// Create a new [`Signal`], with access to other loaded assets.
+    ///
+    /// This is what the play systems call, once [`is_loaded`](Self::is_loaded)
+    /// returns `true`. Defaults to [`to_signal`](Self::to_signal).
+    fn to_signal_with_context(
+        &self,
+        settings: Self::Settings,
+        _context: &SignalContext,
+    ) -> Self::Signal {
+        self.to_signal(settings)
+    }
+}
+
+/// Read access to loaded audio while a [`ToSignal`] source creates its signal.
+///
+/// This lets sources that reference other audio, such as sound banks, build
+/// their signal from it. See [`ToSignal::to_signal_with_context`].
+#[derive(SystemParam)]
+pub struct SignalContext<'w> {
+    mono: Option<Res<'w, Assets<AudioSource<[Sample; 1]>>>>,
+    stereo: Option<Res<'w, Assets<AudioSource<[Sample; 2]>>>>,
+    asset_server: Option<Res<'w, AssetServer>>,
+}
+
+impl SignalContext<'_> {
+    /// Get a loaded mono [`AudioSource`].
+    #[must_use]
+    pub fn mono(
+        &self,
+        handle: &BevyHandle<AudioSource<[Sample; 1]>>,
+    ) -> Option<&AudioSource<[Sample; 1]>> {
+        self.mono.as_ref()?.get(handle)
+    }
+
+    /// Get a loaded stereo [`AudioSource`].
+    #[must_use]
+    pub fn stereo(
+        &self,
+        handle: &BevyHandle<AudioSource<[Sample; 2]>>,
+    ) -> Option<&AudioSource<[Sample; 2]>> {
+        self.stereo.as_ref()?.get(handle)
+    }
+
+    /// The load state of an asset.
+    ///
+    /// This is [`LoadState::NotLoaded`] for assets that were not loaded through the [`AssetServer`].
+    #[must_use]
+    pub fn load_state(&self, handle: impl Into<HandleId>) -> LoadState {
+        self.asset_server
+            .as_ref()
+            .map_or(LoadState::NotLoaded, |server| server.get_load_state(handle))
+    }
+}
+
+/// Settings for playing an [`AudioSource`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AudioSourceSettings {
+    /// Seconds into the source to start playing from. May be negative.
+    pub start: f64,
+    /// How frames are interpolated when the source is played at a different sample rate or speed.
+    pub interpolation: Interpolation,
+    /// Whether to keep repeating the [loop region](Cues::loop_region) of the source,
+    /// or all of it if it has none.
+    pub looping: bool,
+}
+
+impl AudioSourceSettings {
+    /// Generate settings for [`AudioSource`], with [`Interpolation::Linear`].
+    #[must_use]
+    pub fn new(start: f64) -> Self {
+        Self {
+            start,
+            interpolation: Interpolation::default(),
+            looping: false,
+        }
+    }
+
+    /// Interpolate frames with `interpolation`.
+    #[must_use]
+    pub fn with_interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    /// Keep repeating the loop region of the source once it is reached.
+    #[must_use]
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+}
+
+impl From<f64> for AudioSourceSettings {
+    fn from(start: f64) -> Self {
+        Self::new(start)
+    }
 }
 
 impl<F: Frame + Send + Sync + Copy> ToSignal for AudioSource<F> {
-    type Settings = f64;
-    type Signal = Gain<Speed<FramesSignal<F>>>;
+    type Settings = AudioSourceSettings;
+    type Signal = Gain<Speed<InterpolatedFrames<F>>>;
 
     fn to_signal(&self, settings: Self::Settings) -> Self::Signal {
-        Gain::new(Speed::new(FramesSignal::new(self.frames.clone(), settings)))
+        let mut signal =
+            InterpolatedFrames::new(self.frames.clone(), settings.start, settings.interpolation);
+        if settings.looping {
+            let region = self.cues.loop_region.clone();
+            signal = signal.with_loop(region.unwrap_or(0..self.frames.len()));
+        }
+        Gain::new(Speed::new(signal))
     }
 }
 
 #[derive(Resource)]
 struct StreamConfig(SupportedStreamConfigRange);
 
+/// How files with more channels than needed are loaded.
+///
+/// Files with up to eight channels load as an [`AudioSource<[Sample; N]>`](AudioSource)
+/// with a channel for each channel of the file, in the default channel order of WAVE files.
+/// Only mono and stereo sources can be played, so surround files are usually downmixed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Downmix {
+    /// Keep every channel of the file.
+    #[default]
+    Keep,
+    /// Downmix files with more than two channels to stereo.
+    ///
+    /// The center and surround channels are mixed in at -3 dB, and the LFE channel is dropped.
+    Stereo,
+    /// Downmix files with more than one channel to mono, by averaging their stereo downmix.
+    Mono,
+}
+
 /// Adds support for audio playback in a Bevy application.
 ///
 /// Add this plugin to your Bevy app to get access to the [`Audio`] resource.
 #[derive(Default)]
 pub struct AudioPlugin {
     stream_config: Mutex<Option<SupportedStreamConfigRange>>,
+    #[cfg(any(feature = "flac", feature = "mp3", feature = "ogg", feature = "wav"))]
+    load_options: loader::LoadOptions,
+    #[cfg(any(feature = "flac", feature = "mp3", feature = "ogg", feature = "wav"))]
+    resample_on_load: bool,
 }
 
 impl AudioPlugin {
@@ -174,8 +557,52 @@ impl AudioPlugin {
     pub fn with_stream_config(stream_config: SupportedStreamConfigRange) -> Self {
         Self {
             stream_config: Mutex::new(Some(stream_config)),
+            #[cfg(any(feature = "flac", feature = "mp3", feature = "ogg", feature = "wav"))]
+            load_options: loader::LoadOptions::default(),
+            #[cfg(any(feature = "flac", feature = "mp3", feature = "ogg", feature = "wav"))]
+            resample_on_load: false,
         }
     }
+
+    /// Downmix files with more channels than needed when they are loaded.
+    #[cfg(any(feature = "flac", feature = "mp3", feature = "ogg", feature = "wav"))]
+    #[must_use]
+    pub fn with_downmix(mut self, downmix: Downmix) -> Self {
+        self.load_options.downmix = downmix;
+        self
+    }
+
+    /// Also load every file as mono and stereo, labeled `mono` and `stereo`.
+    ///
+    /// This allows loading any file as the layout needed, regardless of its channels:
+    ///
+    /// ```ignore
+    /// let music: Handle<AudioSource<[Sample; 2]>> = asset_server.load("mono.wav#stereo");
+    /// ```
+    ///
+    /// Mono files are upmixed by duplicating their channel, and other files are
+    /// downmixed as with [`Downmix`]. Layouts matching the file share its frames,
+    /// while others take extra memory, so this is disabled by default.
+    #[cfg(any(feature = "flac", feature = "mp3", feature = "ogg", feature = "wav"))]
+    #[must_use]
+    pub fn with_channel_labels(mut self, channel_labels: bool) -> Self {
+        self.load_options.channel_labels = channel_labels;
+        self
+    }
+
+    /// Resample files to the sample rate of the audio output when they are loaded.
+    ///
+    /// Files are otherwise resampled while they play, with linear interpolation,
+    /// costing CPU time for every voice. Resampling on load uses a windowed sinc
+    /// filter, which sounds better but makes loading slower.
+    ///
+    /// [`stream::StreamingAudioSource`]s keep their sample rate.
+    #[cfg(any(feature = "flac", feature = "mp3", feature = "ogg", feature = "wav"))]
+    #[must_use]
+    pub fn with_resample_on_load(mut self, resample_on_load: bool) -> Self {
+        self.resample_on_load = resample_on_load;
+        self
+    }
 }
 
 impl Plugin for AudioPlugin {
@@ -186,21 +613,90 @@ impl Plugin for AudioPlugin {
             .add_audio_source::<[Sample; 1], AudioSource<[Sample; 1]>>()
             .add_audio_source::<[Sample; 2], AudioSource<[Sample; 2]>>()
             .add_audio_source::<Sample, builtins::sine::Sine>()
+            .add_audio_source::<[Sample; 1], LayeredSound<AudioSource<[Sample; 1]>>>()
+            .add_audio_source::<[Sample; 2], LayeredSound<AudioSource<[Sample; 2]>>>()
+            .add_audio_source::<[Sample; 1], RandomSound<[Sample; 1]>>()
+            .add_audio_source::<[Sample; 2], RandomSound<[Sample; 2]>>()
+            .add_audio_source::<[Sample; 1], StemMusic<[Sample; 1]>>()
+            .add_audio_source::<[Sample; 2], StemMusic<[Sample; 2]>>()
+            .add_audio_source::<[Sample; 1], DynamicSignal<[Sample; 1]>>()
+            .add_audio_source::<Sample, DynamicSignal<Sample>>()
+            .add_audio_source::<[Sample; 2], DynamicSignal<[Sample; 2]>>()
             .init_resource::<SpatialAudioOutput>()
-            .add_spatial_audio_source::<builtins::sine::Sine>();
+            .add_spatial_audio_source::<builtins::sine::Sine>()
+            .add_spatial_audio_source::<DynamicSpatialSignal>()
+            .init_resource::<AudioClock<AudioOutput<[Sample; 1]>>>()
+            .init_resource::<AudioClock<AudioOutput<Sample>>>()
+            .init_resource::<AudioClock<AudioOutput<[Sample; 2]>>>()
+            .init_resource::<AudioClock<SpatialAudioOutput>>()
+            .add_systems(
+                PreUpdate,
+                (
+                    update_audio_clock::<AudioOutput<[Sample; 1]>>,
+                    update_audio_clock::<AudioOutput<Sample>>,
+                    update_audio_clock::<AudioOutput<[Sample; 2]>>,
+                    update_audio_clock::<SpatialAudioOutput>,
+                ),
+            )
+            .init_resource::<MusicPlayer>()
+            .add_event::<TrackChanged>()
+            .init_resource::<InteractiveMusic>()
+            .add_event::<SectionChanged>()
+            .add_systems(
+                PostUpdate,
+                (update_music_player, update_interactive_music)
+                    .before(play_queued_audio::<[Sample; 2], AudioSource<[Sample; 2]>>),
+            );
 
         if let Some(stream_config) = self.stream_config.lock().unwrap().take() {
             app.insert_resource(StreamConfig(stream_config));
         }
 
+        #[cfg(any(feature = "flac", feature = "mp3", feature = "ogg", feature = "wav"))]
+        let mut load_options = self.load_options.clone();
+        #[cfg(any(feature = "flac", feature = "mp3", feature = "ogg", feature = "wav"))]
+        if self.resample_on_load {
+            load_options.resample_to = Some(
+                app.world
+                    .resource::<AudioOutput<[Sample; 2]>>()
+                    .clock()
+                    .clone(),
+            );
+        }
+        #[cfg(any(feature = "flac", feature = "mp3", feature = "ogg", feature = "wav"))]
+        app.add_asset::<AudioSource<[Sample; 3]>>()
+            .add_asset::<AudioSource<[Sample; 4]>>()
+            .add_asset::<AudioSource<[Sample; 5]>>()
+            .add_asset::<AudioSource<[Sample; 6]>>()
+            .add_asset::<AudioSource<[Sample; 7]>>()
+            .add_asset::<AudioSource<[Sample; 8]>>()
+            .add_asset::<metadata::AudioMetadata>();
         #[cfg(feature = "flac")]
-        app.init_asset_loader::<loader::flac_loader::FlacLoader>();
+        app.add_asset_loader(loader::flac_loader::FlacLoader {
+            options: load_options.clone(),
+        });
         #[cfg(feature = "mp3")]
-        app.init_asset_loader::<loader::mp3_loader::Mp3Loader>();
+        app.add_asset_loader(loader::mp3_loader::Mp3Loader {
+            options: load_options.clone(),
+        });
         #[cfg(feature = "ogg")]
-        app.init_asset_loader::<loader::ogg_loader::OggLoader>();
+        app.add_asset_loader(loader::ogg_loader::OggLoader {
+            options: load_options.clone(),
+        });
         #[cfg(feature = "wav")]
-        app.init_asset_loader::<loader::wav_loader::WavLoader>();
+        app.add_asset_loader(loader::wav_loader::WavLoader {
+            options: load_options.clone(),
+        });
+        #[cfg(any(feature = "flac", feature = "mp3", feature = "ogg", feature = "wav"))]
+        app.add_audio_source::<[Sample; 1], stream::StreamingAudioSource<[Sample; 1]>>()
+            .add_audio_source::<[Sample; 2], stream::StreamingAudioSource<[Sample; 2]>>()
+            .add_asset_loader(loader::stream_loader::StreamLoader {
+                downmix: load_options.downmix,
+            });
+        #[cfg(feature = "sound_event")]
+        app.add_audio_source::<[Sample; 2], sound_event::SoundEvent<[Sample; 2]>>()
+            .add_spatial_buffered_audio_source::<sound_event::SoundEvent<Sample>>()
+            .init_asset_loader::<loader::sound_event_loader::SoundEventLoader>();
     }
 }
 
@@ -256,7 +752,13 @@ impl AudioApp for App {
             .add_asset::<AudioSink<Source>>()
             .init_resource::<Audio<F, Source>>()
             .init_resource::<AudioSinks<Source>>()
-            .add_systems(PostUpdate, play_queued_audio::<F, Source>)
+            .add_systems(
+                PostUpdate,
+                (
+                    play_queued_audio::<F, Source>,
+                    clean_stopped_audio_sinks::<Source>,
+                ),
+            )
     }
 
     fn add_spatial_audio_source<Source>(&mut self) -> &mut Self
@@ -268,7 +770,13 @@ impl AudioApp for App {
             .add_asset::<SpatialAudioSink<Source>>()
             .init_resource::<Audio<Sample, Source>>()
             .init_resource::<SpatialAudioSinks<Source>>()
-            .add_systems(PostUpdate, play_queued_spatial_audio::<Source>)
+            .add_systems(
+                PostUpdate,
+                (
+                    play_queued_spatial_audio::<Source>,
+                    clean_stopped_spatial_audio_sinks::<Source>,
+                ),
+            )
     }
 
     fn add_spatial_buffered_audio_source<Source>(&mut self) -> &mut Self
@@ -280,7 +788,13 @@ impl AudioApp for App {
             .add_asset::<SpatialBufferedAudioSink<Source>>()
             .init_resource::<Audio<Sample, Source>>()
             .init_resource::<SpatialBufferedAudioSinks<Source>>()
-            .add_systems(PostUpdate, play_queued_spatial_buffered_audio::<Source>)
+            .add_systems(
+                PostUpdate,
+                (
+                    play_queued_spatial_buffered_audio::<Source>,
+                    clean_stopped_spatial_buffered_audio_sinks::<Source>,
+                ),
+            )
     }
 }
 
@@ -1,8 +1,129 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+#[cfg(any(feature = "flac", feature = "mp3", feature = "ogg", feature = "wav"))]
+mod channels;
 #[cfg(feature = "flac")]
-pub mod flac_loader;
+pub(crate) mod flac_loader;
 #[cfg(feature = "mp3")]
-pub mod mp3_loader;
+pub(crate) mod mp3_loader;
 #[cfg(feature = "ogg")]
-pub mod ogg_loader;
+pub(crate) mod ogg_loader;
+#[cfg(any(feature = "flac", feature = "mp3", feature = "wav"))]
+mod pcm;
+#[cfg(any(feature = "flac", feature = "mp3", feature = "ogg", feature = "wav"))]
+mod resample;
+#[cfg(feature = "sound_event")]
+pub(crate) mod sound_event_loader;
+#[cfg(any(feature = "flac", feature = "mp3", feature = "ogg", feature = "wav"))]
+pub(crate) mod stream_loader;
 #[cfg(feature = "wav")]
-pub mod wav_loader;
+pub(crate) mod wav_loader;
+
+/// Error returned when an audio file fails to load.
+///
+/// The asset server only logs load errors, but the error can be told apart
+/// from others by downcasting, e.g. in a custom [`AssetLoader`](bevy::asset::AssetLoader)
+/// wrapping the ones from this crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AudioLoadError {
+    /// The file is encoded in a way that isn't supported, such as an unknown
+    /// extension, or a codec feature the decoder lacks.
+    UnsupportedFormat(String),
+    /// The file has no channels, or more than eight of them.
+    UnsupportedChannelCount(usize),
+    /// The file stores samples with a bit depth that isn't supported.
+    UnsupportedBitDepth(u32),
+    /// The file is corrupt or truncated.
+    CorruptData {
+        /// How far into the file the decoder had read when it failed.
+        ///
+        /// Decoders read ahead, so the corrupt data is at or before this offset.
+        offset: u64,
+        /// What the decoder found wrong.
+        reason: String,
+    },
+}
+
+impl Display for AudioLoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AudioLoadError::UnsupportedFormat(format) => write!(f, "unsupported format: {format}"),
+            AudioLoadError::UnsupportedChannelCount(channels) => write!(
+                f,
+                "unsupported channel count: {channels}, only 1 to 8 channels are supported"
+            ),
+            AudioLoadError::UnsupportedBitDepth(bits) => {
+                write!(f, "unsupported bit depth: {bits} bits per sample")
+            }
+            AudioLoadError::CorruptData { offset, reason } => {
+                write!(f, "corrupt data at or before byte {offset}: {reason}")
+            }
+        }
+    }
+}
+
+impl Error for AudioLoadError {}
+
+/// Options shared by the loaders of encoded audio files.
+#[cfg(any(feature = "flac", feature = "mp3", feature = "ogg", feature = "wav"))]
+#[derive(Clone, Default)]
+pub(crate) struct LoadOptions {
+    pub(crate) downmix: crate::Downmix,
+    /// Whether to add the `mono` and `stereo` labeled assets.
+    pub(crate) channel_labels: bool,
+    /// Clock of the output to resample files to the sample rate of.
+    pub(crate) resample_to: Option<std::sync::Arc<crate::clock::Clock>>,
+}
+
+/// Interleaved samples of a decoded file.
+#[cfg(any(feature = "flac", feature = "mp3", feature = "ogg", feature = "wav"))]
+pub(crate) struct Decoded {
+    pub(crate) sample_rate: u32,
+    pub(crate) channels: usize,
+    pub(crate) samples: Vec<oddio::Sample>,
+    /// Loop points and markers, in frames.
+    pub(crate) cues: crate::cues::Cues,
+    pub(crate) format: crate::metadata::AudioFormat,
+    /// Bits per sample, for lossless formats.
+    pub(crate) bits_per_sample: Option<u32>,
+    /// Tags as they appear in the file, with keys renamed to their Vorbis comment names.
+    pub(crate) tags: Vec<(String, String)>,
+}
+
+/// Reader that keeps track of how far the decoder has read, to report corrupt data.
+///
+/// The position is kept in `P`, which is a `&AtomicU64`, or an `Arc<AtomicU64>` for
+/// decoders that outlive the loader.
+#[cfg(any(feature = "flac", feature = "ogg", feature = "wav"))]
+pub(crate) struct Tracked<R, P> {
+    pub(crate) inner: R,
+    pub(crate) position: P,
+}
+
+#[cfg(any(feature = "flac", feature = "ogg", feature = "wav"))]
+impl<R: std::io::Read, P: std::ops::Deref<Target = std::sync::atomic::AtomicU64>> std::io::Read
+    for Tracked<R, P>
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.position
+            .fetch_add(read as u64, std::sync::atomic::Ordering::Relaxed);
+        Ok(read)
+    }
+}
+
+#[cfg(any(feature = "flac", feature = "ogg", feature = "wav"))]
+impl<R: std::io::Seek, P: std::ops::Deref<Target = std::sync::atomic::AtomicU64>> std::io::Seek
+    for Tracked<R, P>
+{
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        let position = self.inner.seek(pos)?;
+        self.position
+            .store(position, std::sync::atomic::Ordering::Relaxed);
+        Ok(position)
+    }
+}
@@ -0,0 +1,335 @@
+//! Channel layouts of decoded files, and downmixing them at load time.
+
+use {
+    super::{resample::resample, AudioLoadError, Decoded, LoadOptions},
+    crate::{cues::Cues, metadata::AudioMetadata, AudioSource, Downmix},
+    bevy::{
+        asset::{LoadContext, LoadedAsset},
+        utils::HashMap,
+    },
+    oddio::{Frames, Sample},
+    std::{f32::consts::FRAC_1_SQRT_2, sync::Arc, time::Duration},
+};
+
+/// Most channels a file can have.
+pub(crate) const MAX_CHANNELS: usize = 8;
+
+/// Order of the channels of a file, for files with more than two channels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ChannelOrder {
+    /// The default order of WAVE files, which FLAC also uses.
+    Wave,
+    /// The order of Vorbis streams, which puts the center channel second.
+    #[cfg_attr(not(feature = "ogg"), allow(dead_code))]
+    Vorbis,
+}
+
+impl ChannelOrder {
+    /// Index in the source frame of every channel in WAVE order.
+    fn wave_indices(self, channels: usize) -> &'static [usize] {
+        match (self, channels) {
+            (ChannelOrder::Wave, _) => &[0, 1, 2, 3, 4, 5, 6, 7][..channels],
+            (ChannelOrder::Vorbis, 3) => &[0, 2, 1],
+            (ChannelOrder::Vorbis, 5) => &[0, 2, 1, 3, 4],
+            (ChannelOrder::Vorbis, 6) => &[0, 2, 1, 5, 3, 4],
+            (ChannelOrder::Vorbis, 7) => &[0, 2, 1, 6, 5, 3, 4],
+            (ChannelOrder::Vorbis, 8) => &[0, 2, 1, 7, 5, 6, 3, 4],
+            (ChannelOrder::Vorbis, _) => &[0, 1, 2, 3][..channels],
+        }
+    }
+}
+
+/// Left and right gains of every channel in WAVE order, when downmixing to stereo.
+///
+/// The center and surround channels are mixed in at -3 dB, and the LFE channel is dropped.
+fn stereo_gains(channels: usize) -> &'static [(f32, f32)] {
+    const L: (f32, f32) = (1.0, 0.0);
+    const R: (f32, f32) = (0.0, 1.0);
+    const C: (f32, f32) = (FRAC_1_SQRT_2, FRAC_1_SQRT_2);
+    const LFE: (f32, f32) = (0.0, 0.0);
+    const SL: (f32, f32) = (FRAC_1_SQRT_2, 0.0);
+    const SR: (f32, f32) = (0.0, FRAC_1_SQRT_2);
+
+    match channels {
+        1 => &[C],
+        2 => &[L, R],
+        3 => &[L, R, C],
+        4 => &[L, R, SL, SR],
+        5 => &[L, R, C, SL, SR],
+        6 => &[L, R, C, LFE, SL, SR],
+        // The back center channel.
+        7 => &[L, R, C, LFE, (0.5, 0.5), SL, SR],
+        _ => &[L, R, C, LFE, SL, SR, SL, SR],
+    }
+}
+
+/// Reorder interleaved samples into WAVE order, and downmix them if needed.
+///
+/// Returns the number of channels left.
+pub(crate) fn prepare(
+    samples: &mut Vec<Sample>,
+    channels: usize,
+    order: ChannelOrder,
+    downmix: Downmix,
+) -> Result<usize, AudioLoadError> {
+    if channels == 0 || channels > MAX_CHANNELS {
+        return Err(AudioLoadError::UnsupportedChannelCount(channels));
+    }
+
+    let target = match downmix {
+        Downmix::Stereo if channels > 2 => 2,
+        Downmix::Mono if channels > 1 => 1,
+        _ => channels,
+    };
+    if target == channels && order == ChannelOrder::Wave {
+        return Ok(channels);
+    }
+
+    let indices = order.wave_indices(channels);
+    let gains = stereo_gains(channels);
+    let mut frame = [0.0; MAX_CHANNELS];
+    let mut len = 0;
+    // Every frame shrinks or keeps its size, so it can be written in place.
+    for start in (0..samples.len() - samples.len() % channels).step_by(channels) {
+        for (channel, &index) in frame.iter_mut().zip(indices) {
+            *channel = samples[start + index];
+        }
+        let frame = &frame[..channels];
+        if target == channels {
+            samples[len..len + channels].copy_from_slice(frame);
+        } else {
+            let (left, right) = frame
+                .iter()
+                .zip(gains)
+                .fold((0.0, 0.0), |(left, right), (sample, (l, r))| {
+                    (left + sample * l, right + sample * r)
+                });
+            if target == 2 {
+                samples[len] = left;
+                samples[len + 1] = right;
+            } else {
+                samples[len] = (left + right) * 0.5;
+            }
+        }
+        len += target;
+    }
+    samples.truncate(len);
+
+    Ok(target)
+}
+
+/// Convert interleaved samples in WAVE order to stereo, duplicating mono samples.
+fn stereo_samples(samples: &[Sample], channels: usize) -> Vec<Sample> {
+    if channels == 1 {
+        return samples
+            .iter()
+            .flat_map(|&sample| [sample, sample])
+            .collect();
+    }
+    let mut samples = samples.to_vec();
+    // The layout was checked by `prepare` already.
+    let _ = prepare(&mut samples, channels, ChannelOrder::Wave, Downmix::Stereo);
+    samples
+}
+
+/// Convert interleaved samples in WAVE order to mono.
+fn mono_samples(samples: &[Sample], channels: usize) -> Vec<Sample> {
+    let mut samples = samples.to_vec();
+    let _ = prepare(&mut samples, channels, ChannelOrder::Wave, Downmix::Mono);
+    samples
+}
+
+fn frames<const N: usize>(sample_rate: u32, samples: &[Sample]) -> Arc<Frames<[Sample; N]>> {
+    Frames::from_iter(
+        sample_rate,
+        samples
+            .chunks_exact(N)
+            .map(|frame| <[Sample; N]>::try_from(frame).unwrap()),
+    )
+}
+
+/// Move a position in frames from one sample rate to another.
+#[allow(clippy::cast_possible_truncation)]
+fn rescale(frame: usize, from: u32, to: u32) -> usize {
+    let (from, to) = (u64::from(from), u64::from(to));
+    ((frame as u64 * to + from / 2) / from) as usize
+}
+
+/// Load interleaved samples as the [`AudioSource`] with a frame for each channel.
+///
+/// With [`LoadOptions::resample_to`], the samples and their cues are resampled to the rate of the output.
+/// With [`LoadOptions::channel_labels`], they are also loaded as mono and stereo,
+/// labeled `mono` and `stereo`. See [`prepare`].
+///
+/// The details and tags of the file are loaded as the [`AudioMetadata`] labeled `metadata`.
+pub(crate) fn set_audio_source(
+    load_context: &mut LoadContext,
+    decoded: Decoded,
+    order: ChannelOrder,
+    options: &LoadOptions,
+) -> Result<(), AudioLoadError> {
+    fn set<const N: usize>(
+        load_context: &mut LoadContext,
+        frames: Arc<Frames<[Sample; N]>>,
+        cues: &Cues,
+    ) {
+        load_context.set_default_asset(LoadedAsset::new(
+            AudioSource::new(frames).with_cues(cues.clone()),
+        ));
+    }
+
+    let Decoded {
+        mut sample_rate,
+        channels,
+        mut samples,
+        mut cues,
+        format,
+        bits_per_sample,
+        tags,
+    } = decoded;
+    let file_channels = channels;
+    let channels = prepare(&mut samples, channels, order, options.downmix)?;
+    #[allow(clippy::cast_precision_loss)]
+    let duration = (samples.len() / channels) as f64 / f64::from(sample_rate);
+    let mut metadata = AudioMetadata {
+        format,
+        sample_rate,
+        channels: file_channels,
+        bits_per_sample,
+        duration: Duration::try_from_secs_f64(duration).unwrap_or_default(),
+        tags: HashMap::default(),
+    };
+    for (key, value) in tags {
+        metadata
+            .tags
+            .entry(key.to_uppercase())
+            .or_default()
+            .push(value);
+    }
+    load_context.set_labeled_asset("metadata", LoadedAsset::new(metadata));
+    // An output without a stream has no rate.
+    if let Some(output_rate) = options
+        .resample_to
+        .as_ref()
+        .map(|clock| clock.sample_rate())
+        .filter(|&output_rate| output_rate != 0 && output_rate != sample_rate)
+    {
+        samples = resample(&samples, channels, sample_rate, output_rate);
+        let rescale = |frame| rescale(frame, sample_rate, output_rate);
+        if let Some(region) = &mut cues.loop_region {
+            *region = rescale(region.start)..rescale(region.end);
+        }
+        for marker in &mut cues.markers {
+            marker.frame = rescale(marker.frame);
+        }
+        sample_rate = output_rate;
+    }
+
+    let mut mono = None;
+    let mut stereo = None;
+    match channels {
+        1 => {
+            let frames = frames::<1>(sample_rate, &samples);
+            set(load_context, frames.clone(), &cues);
+            mono = Some(frames);
+        }
+        2 => {
+            let frames = frames::<2>(sample_rate, &samples);
+            set(load_context, frames.clone(), &cues);
+            stereo = Some(frames);
+        }
+        3 => set(load_context, frames::<3>(sample_rate, &samples), &cues),
+        4 => set(load_context, frames::<4>(sample_rate, &samples), &cues),
+        5 => set(load_context, frames::<5>(sample_rate, &samples), &cues),
+        6 => set(load_context, frames::<6>(sample_rate, &samples), &cues),
+        7 => set(load_context, frames::<7>(sample_rate, &samples), &cues),
+        _ => set(load_context, frames::<8>(sample_rate, &samples), &cues),
+    }
+
+    if options.channel_labels {
+        // Share the frames of the default asset if it has the same layout.
+        let mono = mono.unwrap_or_else(|| frames(sample_rate, &mono_samples(&samples, channels)));
+        let stereo =
+            stereo.unwrap_or_else(|| frames(sample_rate, &stereo_samples(&samples, channels)));
+        load_context.set_labeled_asset(
+            "mono",
+            LoadedAsset::new(AudioSource::new(mono).with_cues(cues.clone())),
+        );
+        load_context.set_labeled_asset(
+            "stereo",
+            LoadedAsset::new(AudioSource::new(stereo).with_cues(cues)),
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use {
+        super::{mono_samples, prepare, stereo_samples, ChannelOrder},
+        crate::{loader::AudioLoadError, Downmix},
+        std::f32::consts::FRAC_1_SQRT_2,
+    };
+
+    #[test]
+    fn keeps_channels() {
+        let mut samples = vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6];
+        let channels = prepare(&mut samples, 6, ChannelOrder::Wave, Downmix::Keep).unwrap();
+        assert_eq!(channels, 6);
+        assert_eq!(samples, [0.1, 0.2, 0.3, 0.4, 0.5, 0.6]);
+
+        // Stereo files are never downmixed to stereo.
+        let mut samples = vec![0.1, 0.2];
+        let channels = prepare(&mut samples, 2, ChannelOrder::Wave, Downmix::Stereo).unwrap();
+        assert_eq!(channels, 2);
+        assert_eq!(samples, [0.1, 0.2]);
+    }
+
+    #[test]
+    fn reorders_vorbis() {
+        // L, C, R, Ls, Rs, LFE
+        let mut samples = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        prepare(&mut samples, 6, ChannelOrder::Vorbis, Downmix::Keep).unwrap();
+        // L, R, C, LFE, Ls, Rs
+        assert_eq!(samples, [1.0, 3.0, 2.0, 6.0, 4.0, 5.0]);
+    }
+
+    #[test]
+    fn downmixes_surround() {
+        // Two frames of 5.1: the front left channel, then the center channel.
+        let mut samples = vec![1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0];
+        let channels = prepare(&mut samples, 6, ChannelOrder::Wave, Downmix::Stereo).unwrap();
+        assert_eq!(channels, 2);
+        assert_eq!(samples, [1.0, 0.0, FRAC_1_SQRT_2, FRAC_1_SQRT_2]);
+
+        let mut samples = vec![1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0];
+        let channels = prepare(&mut samples, 6, ChannelOrder::Wave, Downmix::Mono).unwrap();
+        assert_eq!(channels, 1);
+        assert_eq!(samples, [0.5, FRAC_1_SQRT_2]);
+    }
+
+    #[test]
+    fn rejects_unknown_layouts() {
+        assert_eq!(
+            prepare(&mut vec![0.0; 9], 9, ChannelOrder::Wave, Downmix::Stereo),
+            Err(AudioLoadError::UnsupportedChannelCount(9))
+        );
+        assert_eq!(
+            prepare(&mut Vec::new(), 0, ChannelOrder::Wave, Downmix::Keep),
+            Err(AudioLoadError::UnsupportedChannelCount(0))
+        );
+    }
+
+    #[test]
+    fn converts_between_mono_and_stereo() {
+        let stereo = stereo_samples(&[0.25, -0.5], 1);
+        assert_eq!(stereo, [0.25, 0.25, -0.5, -0.5]);
+        assert_eq!(mono_samples(&stereo, 2), [0.25, -0.5]);
+
+        // Surround channels are downmixed.
+        let stereo = stereo_samples(&[0.0, 0.0, 1.0, 0.0, 0.0, 0.0], 6);
+        assert_eq!(stereo, [FRAC_1_SQRT_2, FRAC_1_SQRT_2]);
+    }
+}
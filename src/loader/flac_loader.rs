@@ -1,61 +1,86 @@
 use {
-    crate::AudioSource,
-    bevy::asset::{AssetLoader, BoxedFuture, Error, LoadContext, LoadedAsset},
+    super::{
+        channels::{set_audio_source, ChannelOrder},
+        pcm::{check_bit_depth, int_to_sample},
+        AudioLoadError, Decoded, LoadOptions, Tracked,
+    },
+    crate::{cues::Cues, metadata::AudioFormat},
+    bevy::asset::{AssetLoader, BoxedFuture, Error, LoadContext},
     claxon::FlacReader,
+    std::sync::atomic::{AtomicU64, Ordering},
 };
 
 #[derive(Default)]
-pub struct FlacLoader;
-
-impl AssetLoader for FlacLoader {
-    fn load<'a>(
-        &'a self,
-        bytes: &'a [u8],
-        load_context: &'a mut LoadContext,
-    ) -> BoxedFuture<'a, Result<(), Error>> {
-        #[allow(clippy::cast_precision_loss)]
-        Box::pin(async move {
-            let mut reader = FlacReader::new(bytes)?;
+pub struct FlacLoader {
+    pub(crate) options: LoadOptions,
+}
 
-            let channels = reader.streaminfo().channels;
+pub(crate) fn flac_error(err: claxon::Error, offset: u64) -> AudioLoadError {
+    match err {
+        claxon::Error::Unsupported(feature) => AudioLoadError::UnsupportedFormat(feature.into()),
+        claxon::Error::FormatError(reason) => AudioLoadError::CorruptData {
+            offset,
+            reason: reason.into(),
+        },
+        claxon::Error::IoError(err) => AudioLoadError::CorruptData {
+            offset,
+            reason: err.to_string(),
+        },
+    }
+}
 
-            let sample_rate = reader.streaminfo().sample_rate;
+fn decode(bytes: &[u8]) -> Result<Decoded, AudioLoadError> {
+    let position = AtomicU64::new(0);
+    let error = |err| flac_error(err, position.load(Ordering::Relaxed));
 
-            let samples: Vec<i32> = reader.samples().collect::<Result<_, _>>()?;
+    let mut reader = FlacReader::new(Tracked {
+        inner: bytes,
+        position: &position,
+    })
+    .map_err(error)?;
 
-            let convert_i32_to_f32 = |int| {
-                if int < 0 {
-                    (int as f32) / -(i32::MIN as f32)
-                } else {
-                    (int as f32) / (i32::MAX as f32)
-                }
-            };
+    let channels = reader.streaminfo().channels;
 
-            let mut samples: Vec<f32> = samples.into_iter().map(convert_i32_to_f32).collect();
+    let sample_rate = reader.streaminfo().sample_rate;
 
-            match channels {
-                1 => {
-                    let frames = oddio::Frames::from_iter(
-                        sample_rate,
-                        samples.into_iter().map(|frame| [frame]),
-                    );
+    let bits_per_sample = reader.streaminfo().bits_per_sample;
+    check_bit_depth(bits_per_sample)?;
 
-                    let audio_source = AudioSource { frames };
+    let tags = reader
+        .tags()
+        .map(|(key, value)| (key.to_owned(), value.to_owned()))
+        .collect();
 
-                    load_context.set_default_asset(LoadedAsset::new(audio_source));
-                }
-                2 => {
-                    let frames = oddio::Frames::from_iter(
-                        sample_rate,
-                        oddio::frame_stereo(&mut samples).iter().copied(),
-                    );
+    let samples: Vec<f32> = reader
+        .samples()
+        .map(|sample| sample.map(|sample| int_to_sample(sample, bits_per_sample)))
+        .collect::<Result<_, _>>()
+        .map_err(error)?;
 
-                    let audio_source = AudioSource { frames };
+    Ok(Decoded {
+        sample_rate,
+        channels: channels as usize,
+        samples,
+        cues: Cues::default(),
+        format: AudioFormat::Flac,
+        bits_per_sample: Some(bits_per_sample),
+        tags,
+    })
+}
 
-                    load_context.set_default_asset(LoadedAsset::new(audio_source));
-                }
-                _ => unimplemented!("bevy_oddio only have support for 1 or 2 channels only."),
-            }
+impl AssetLoader for FlacLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            set_audio_source(
+                load_context,
+                decode(bytes)?,
+                ChannelOrder::Wave,
+                &self.options,
+            )?;
 
             Ok(())
         })
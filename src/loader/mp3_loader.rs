@@ -1,12 +1,411 @@
 use {
-    crate::AudioSource,
-    bevy::asset::{AssetLoader, BoxedFuture, Error, LoadContext, LoadedAsset},
-    minimp3::Decoder,
-    oddio::Sample,
+    super::{
+        channels::{set_audio_source, ChannelOrder},
+        pcm::i16_to_sample,
+        AudioLoadError, Decoded, LoadOptions,
+    },
+    crate::{cues::Cues, metadata::AudioFormat},
+    bevy::asset::{AssetLoader, BoxedFuture, Error, LoadContext},
+    minimp3::{ffi, MAX_SAMPLES_PER_FRAME},
+    std::{collections::VecDeque, mem, os::raw::c_int, ptr},
 };
 
 #[derive(Default)]
-pub struct Mp3Loader;
+pub struct Mp3Loader {
+    pub(crate) options: LoadOptions,
+}
+
+/// A decoded MP3 frame.
+pub(crate) struct Mp3Frame<'a> {
+    pub(crate) sample_rate: u32,
+    pub(crate) channels: usize,
+    /// Interleaved samples.
+    pub(crate) samples: &'a [i16],
+}
+
+/// The C decoder of minimp3, which all unsafe code of the MP3 loader is confined to.
+///
+/// [`minimp3::Decoder`] copies its input through a ring buffer that fails the
+/// standard library's debug assertions on recent Rust versions, and that buffer
+/// is pointless when the whole file is already in memory.
+struct RawDecoder(Box<ffi::mp3dec_t>);
+
+/// Frame header returned by [`RawDecoder::decode_frame`].
+struct FrameInfo {
+    /// Bytes consumed, including any skipped data before the frame, or 0 if no
+    /// whole frame is left.
+    bytes: usize,
+    sample_rate: u32,
+    channels: usize,
+    /// Samples decoded per channel.
+    samples: usize,
+}
+
+impl RawDecoder {
+    fn new() -> Self {
+        // SAFETY: `mp3dec_t` only holds integers and floats, for which all zero
+        // bits are valid, and `mp3dec_init` only writes to the decoder it is given.
+        unsafe {
+            let mut decoder: Box<ffi::mp3dec_t> = Box::new(mem::zeroed());
+            ffi::mp3dec_init(ptr::addr_of_mut!(*decoder));
+            Self(decoder)
+        }
+    }
+
+    /// Decode the first frame in `bytes` into `pcm`, or only read its header without `pcm`.
+    ///
+    /// Without `pcm`, [`FrameInfo::samples`] is the number of samples the frame holds,
+    /// and the bit reservoir is left as it was.
+    #[allow(clippy::cast_sign_loss)]
+    fn decode_frame(
+        &mut self,
+        bytes: &[u8],
+        pcm: Option<&mut [i16; MAX_SAMPLES_PER_FRAME]>,
+    ) -> FrameInfo {
+        let mut info = ffi::mp3dec_frame_info_t {
+            frame_bytes: 0,
+            frame_offset: 0,
+            channels: 0,
+            hz: 0,
+            layer: 0,
+            bitrate_kbps: 0,
+        };
+        // Longer inputs are decoded as their first `c_int::MAX` bytes, which
+        // hold many frames.
+        let len = c_int::try_from(bytes.len()).unwrap_or(c_int::MAX);
+        let pcm = pcm.map_or(ptr::null_mut(), |pcm| pcm.as_mut_ptr());
+        // SAFETY: minimp3 reads at most `len` bytes from `bytes`, which are all in
+        // bounds, and never reads past the end of a truncated frame. It writes at
+        // most `MAX_SAMPLES_PER_FRAME` samples to `pcm`, or none if it is null. All
+        // pointers are valid for the whole call and nothing else aliases them.
+        let samples = unsafe {
+            ffi::mp3dec_decode_frame(
+                ptr::addr_of_mut!(*self.0),
+                bytes.as_ptr(),
+                len,
+                pcm,
+                ptr::addr_of_mut!(info),
+            )
+        };
+        // minimp3 never returns negative counts, and never consumes more than it was given.
+        FrameInfo {
+            bytes: (info.frame_bytes.max(0) as usize).min(bytes.len()),
+            sample_rate: info.hz.max(0) as u32,
+            channels: info.channels.max(0) as usize,
+            samples: samples.max(0) as usize,
+        }
+    }
+}
+
+/// Decodes the frames of an MP3 file straight from its bytes.
+pub(crate) struct Mp3Frames<B> {
+    bytes: B,
+    /// End of the frames, before any `ID3v1` tag.
+    end: usize,
+    /// Bytes decoded so far.
+    offset: usize,
+    decoder: RawDecoder,
+    pcm: Box<[i16; MAX_SAMPLES_PER_FRAME]>,
+}
+
+impl<B: AsRef<[u8]>> Mp3Frames<B> {
+    pub(crate) fn new(bytes: B) -> Self {
+        // An ID3v1 tag at the end would be mistaken for a broken frame.
+        let end = bytes.as_ref().len() - id3v1_tag(bytes.as_ref()).len();
+        Self {
+            bytes,
+            end,
+            offset: 0,
+            decoder: RawDecoder::new(),
+            pcm: Box::new([0; MAX_SAMPLES_PER_FRAME]),
+        }
+    }
+
+    /// Decode the next frame, skipping any data in between, such as ID3 tags.
+    ///
+    /// Returns `None` once no frames are left.
+    pub(crate) fn next_frame(&mut self) -> Option<Mp3Frame<'_>> {
+        loop {
+            let bytes = &self.bytes.as_ref()[self.offset..self.end];
+            let info = self.decoder.decode_frame(bytes, Some(&mut self.pcm));
+            if info.bytes == 0 {
+                return None;
+            }
+            self.offset += info.bytes;
+            // Frames without samples only fill the bit reservoir, or were skipped data.
+            if info.samples > 0 {
+                return Some(Mp3Frame {
+                    sample_rate: info.sample_rate,
+                    channels: info.channels,
+                    samples: &self.pcm[..info.samples * info.channels],
+                });
+            }
+        }
+    }
+
+    /// Skip up to `samples` samples per channel, stopping at the start of a frame.
+    ///
+    /// Frames are skipped by their header, without decoding them, except for the
+    /// ones right before the next frame, whose bit reservoir it may use.
+    ///
+    /// Returns how many samples per channel were skipped.
+    pub(crate) fn skip(&mut self, samples: u64) -> u64 {
+        /// Bytes before a frame that its bit reservoir can reach back to, with room
+        /// for the headers and side information of the frames in between.
+        const RESERVOIR_BYTES: usize = 2 * 511;
+
+        // Starts of the skipped frames within reach of the bit reservoir of the next frame.
+        let mut starts = VecDeque::new();
+        let mut skipped = 0;
+        loop {
+            let bytes = &self.bytes.as_ref()[self.offset..self.end];
+            let info = self.decoder.decode_frame(bytes, None);
+            if info.bytes == 0 || skipped + info.samples as u64 > samples {
+                break;
+            }
+            starts.push_back(self.offset);
+            self.offset += info.bytes;
+            skipped += info.samples as u64;
+            while starts.len() > 1 && self.offset - starts[1] > RESERVOIR_BYTES {
+                starts.pop_front();
+            }
+        }
+
+        // Decode the frames within reach, filling the bit reservoir of the next frame.
+        let next = self.offset;
+        self.offset = starts.front().copied().unwrap_or(next);
+        while self.offset < next {
+            let bytes = &self.bytes.as_ref()[self.offset..self.end];
+            let info = self.decoder.decode_frame(bytes, Some(&mut self.pcm));
+            if info.bytes == 0 {
+                break;
+            }
+            self.offset += info.bytes;
+        }
+        self.offset = next;
+        skipped
+    }
+
+    /// Bytes decoded so far.
+    pub(crate) fn offset(&self) -> u64 {
+        self.offset as u64
+    }
+}
+
+/// Decode every frame of an MP3 file.
+fn decode(bytes: &[u8]) -> Result<Decoded, AudioLoadError> {
+    let mut frames = Mp3Frames::new(bytes);
+
+    let mut output: Option<Decoded> = None;
+
+    while let Some(Mp3Frame {
+        sample_rate,
+        channels,
+        samples,
+    }) = frames.next_frame()
+    {
+        let output = output.get_or_insert_with(|| Decoded {
+            sample_rate,
+            channels,
+            samples: Vec::new(),
+            cues: Cues::default(),
+            format: AudioFormat::Mp3,
+            bits_per_sample: None,
+            tags: Vec::new(),
+        });
+        if channels != output.channels {
+            return Err(AudioLoadError::CorruptData {
+                offset: frames.offset(),
+                reason: format!(
+                    "MP3 changes from {} to {channels} channels mid-stream",
+                    output.channels
+                ),
+            });
+        }
+        output
+            .samples
+            .extend(samples.iter().copied().map(i16_to_sample));
+    }
+
+    let mut output = output.ok_or(AudioLoadError::CorruptData {
+        offset: 0,
+        reason: "no MP3 frames found".into(),
+    })?;
+    output.tags = id3v2_tags(bytes).unwrap_or_else(|| id3v1_tags(bytes));
+    Ok(output)
+}
+
+/// Decode the text of an `ID3v2` frame, which may hold several strings separated by nulls.
+fn id3_text(encoding: u8, bytes: &[u8]) -> Vec<String> {
+    match encoding {
+        // UTF-16, with a byte order mark at the start of every string, or big endian without one.
+        1 | 2 => {
+            let units: Vec<u16> = bytes
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            units
+                .split(|&unit| unit == 0)
+                .map(|units| match units {
+                    [0xfffe, units @ ..] => String::from_utf16_lossy(
+                        &units
+                            .iter()
+                            .map(|unit| unit.swap_bytes())
+                            .collect::<Vec<_>>(),
+                    ),
+                    [0xfeff, units @ ..] | units => String::from_utf16_lossy(units),
+                })
+                .collect()
+        }
+        3 => bytes
+            .split(|&byte| byte == 0)
+            .map(|text| String::from_utf8_lossy(text).into_owned())
+            .collect(),
+        // ISO-8859-1, which maps straight to the first code points of Unicode.
+        _ => bytes
+            .split(|&byte| byte == 0)
+            .map(|text| text.iter().copied().map(char::from).collect())
+            .collect(),
+    }
+}
+
+/// Read a 28-bit integer stored in the lower seven bits of four bytes.
+fn syncsafe(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .fold(0, |size, &byte| size << 7 | usize::from(byte & 0x7f))
+}
+
+/// Read the text frames and comments of an ID3v2.3 or ID3v2.4 tag at the start of the file.
+///
+/// Returns `None` if there is no such tag.
+fn id3v2_tags(bytes: &[u8]) -> Option<Vec<(String, String)>> {
+    let header = bytes
+        .get(..10)
+        .filter(|header| header.starts_with(b"ID3"))?;
+    let version = header[3];
+    if !matches!(version, 3 | 4) {
+        return None;
+    }
+    let flags = header[5];
+    let tag = &bytes[10..bytes.len().min(10 + syncsafe(&header[6..]))];
+    // ID3v2.3 escapes false frame syncs in the whole tag, by following 0xff with 0x00.
+    let unsynchronized;
+    let mut tag = if version == 3 && flags & 0x80 != 0 {
+        unsynchronized = tag
+            .iter()
+            .enumerate()
+            .filter(|&(i, &byte)| !(byte == 0 && i > 0 && tag[i - 1] == 0xff))
+            .map(|(_, &byte)| byte)
+            .collect::<Vec<_>>();
+        &unsynchronized[..]
+    } else {
+        tag
+    };
+    if flags & 0x40 != 0 {
+        // Skip the extended header, whose size includes itself only in ID3v2.4.
+        let size = match version {
+            3 => 4 + usize::try_from(u32::from_be_bytes(tag.get(..4)?.try_into().ok()?)).ok()?,
+            _ => syncsafe(tag.get(..4)?),
+        };
+        tag = tag.get(size..)?;
+    }
+
+    let mut tags = Vec::new();
+    while let Some(header) = tag.get(..10) {
+        let id = &header[..4];
+        // Padding follows the last frame.
+        if id[0] == 0 {
+            break;
+        }
+        let size = match version {
+            3 => usize::try_from(u32::from_be_bytes(header[4..8].try_into().ok()?)).ok()?,
+            _ => syncsafe(&header[4..8]),
+        };
+        let Some(data) = tag.get(10..10 + size) else {
+            break;
+        };
+        tag = &tag[10 + size..];
+
+        let Some((&encoding, text)) = data.split_first() else {
+            continue;
+        };
+        let (key, values) = match id {
+            b"TXXX" => {
+                let mut values = id3_text(encoding, text).into_iter();
+                let Some(description) = values.next() else {
+                    continue;
+                };
+                (description, values.take(1).collect())
+            }
+            // Comments start with a language code and a description.
+            b"COMM" => match id3_text(encoding, text.get(3..).unwrap_or_default()).get(1) {
+                Some(comment) => ("COMMENT".to_owned(), vec![comment.clone()]),
+                None => continue,
+            },
+            [b'T', ..] => {
+                let key = match id {
+                    b"TIT2" => "TITLE",
+                    b"TPE1" => "ARTIST",
+                    b"TALB" => "ALBUM",
+                    b"TPE2" => "ALBUMARTIST",
+                    b"TRCK" => "TRACKNUMBER",
+                    b"TPOS" => "DISCNUMBER",
+                    b"TYER" | b"TDRC" => "DATE",
+                    b"TCON" => "GENRE",
+                    b"TCOM" => "COMPOSER",
+                    _ => std::str::from_utf8(id).unwrap_or_default(),
+                };
+                (key.to_owned(), id3_text(encoding, text))
+            }
+            _ => continue,
+        };
+        tags.extend(
+            values
+                .into_iter()
+                .filter(|value| !value.is_empty())
+                .map(|value| (key.clone(), value)),
+        );
+    }
+
+    Some(tags)
+}
+
+/// The `ID3v1` tag at the end of the file, or nothing if there is none.
+fn id3v1_tag(bytes: &[u8]) -> &[u8] {
+    bytes
+        .len()
+        .checked_sub(128)
+        .map(|start| &bytes[start..])
+        .filter(|tag| tag.starts_with(b"TAG"))
+        .unwrap_or_default()
+}
+
+/// Read the `ID3v1` tag at the end of the file, if any.
+fn id3v1_tags(bytes: &[u8]) -> Vec<(String, String)> {
+    let tag = id3v1_tag(bytes);
+    if tag.is_empty() {
+        return Vec::new();
+    }
+    [
+        ("TITLE", &tag[3..33]),
+        ("ARTIST", &tag[33..63]),
+        ("ALBUM", &tag[63..93]),
+        ("DATE", &tag[93..97]),
+        ("COMMENT", &tag[97..127]),
+    ]
+    .into_iter()
+    .filter_map(|(key, field)| {
+        let end = field
+            .iter()
+            .position(|&byte| byte == 0)
+            .unwrap_or(field.len());
+        let value: String = field[..end].iter().copied().map(char::from).collect();
+        let value = value.trim_end();
+        (!value.is_empty()).then(|| (key.to_owned(), value.to_owned()))
+    })
+    .collect()
+}
 
 impl AssetLoader for Mp3Loader {
     fn load<'a>(
@@ -15,50 +414,173 @@ impl AssetLoader for Mp3Loader {
         load_context: &'a mut LoadContext,
     ) -> BoxedFuture<'a, Result<(), Error>> {
         Box::pin(async move {
-            let mut decoder = Decoder::new(bytes);
-
-            let mut samples = Vec::new();
-
-            let mut current_sample_rate = 44100;
-
-            loop {
-                match decoder.next_frame() {
-                    Ok(minimp3::Frame {
-                        data, sample_rate, ..
-                    }) => {
-                        samples.push([data[0], data[1]]);
-                        current_sample_rate = sample_rate;
-                    }
-                    Err(minimp3::Error::Eof) => break,
-                    Err(err) => return Err(err.into()),
-                }
-            }
+            set_audio_source(
+                load_context,
+                decode(bytes)?,
+                ChannelOrder::Wave,
+                &self.options,
+            )?;
 
-            let convert_i32_to_f32 = |int| {
-                if int < 0 {
-                    (f32::from(int)) / -(f32::from(i16::MIN))
-                } else {
-                    (f32::from(int)) / f32::from(i16::MAX)
-                }
-            };
+            Ok(())
+        })
+    }
 
-            let samples: Vec<[Sample; 2]> = samples
-                .into_iter()
-                .map(|[l, r]| [convert_i32_to_f32(l), convert_i32_to_f32(r)])
-                .collect();
+    fn extensions(&self) -> &[&str] {
+        &["mp3"]
+    }
+}
 
-            #[allow(clippy::cast_sign_loss)]
-            let frames = oddio::Frames::from_iter(current_sample_rate as u32, samples);
+#[cfg(test)]
+mod tests {
+    use {
+        super::{decode, Mp3Frames},
+        crate::loader::AudioLoadError,
+    };
 
-            let audio_source = AudioSource { frames };
+    /// Samples in every MPEG-1 Layer III frame, per channel.
+    const FRAME_SAMPLES: usize = 1152;
 
-            load_context.set_default_asset(LoadedAsset::new(audio_source));
+    /// Generate an MP3 file of silent 128 kb/s, 44.1 kHz frames.
+    ///
+    /// Each frame is a header followed by zeroed side information and main
+    /// data, which decodes to silence without any bit reservoir.
+    fn silent_mp3(mono: bool, frames: usize) -> Vec<u8> {
+        // 144 * 128000 / 44100 bytes, without padding.
+        const FRAME_BYTES: usize = 417;
+        let mode = if mono { 0xc4 } else { 0x04 };
 
-            Ok(())
-        })
+        let mut bytes = Vec::with_capacity(frames * FRAME_BYTES);
+        for _ in 0..frames {
+            bytes.extend([0xff, 0xfb, 0x90, mode]);
+            bytes.resize(bytes.len() + FRAME_BYTES - 4, 0);
+        }
+        bytes
     }
 
-    fn extensions(&self) -> &[&str] {
-        &["mp3"]
+    #[test]
+    fn decodes_every_sample() {
+        for (mono, channels) in [(true, 1), (false, 2)] {
+            let decoded = decode(&silent_mp3(mono, 10)).unwrap();
+            assert_eq!(decoded.sample_rate, 44100);
+            assert_eq!(decoded.channels, channels);
+            assert_eq!(decoded.samples.len(), 10 * FRAME_SAMPLES * channels);
+        }
+    }
+
+    #[test]
+    fn skips_leading_garbage() {
+        let mut bytes = b"ID3 or other junk".to_vec();
+        bytes.extend(silent_mp3(true, 10));
+
+        let decoded = decode(&bytes).unwrap();
+        assert_eq!(decoded.channels, 1);
+        assert_eq!(decoded.samples.len(), 10 * FRAME_SAMPLES);
+    }
+
+    #[test]
+    fn reads_id3_tags() {
+        // An ID3v2.3 tag with a Latin-1 title, and a UTF-16 artist with two values.
+        let mut frames = Vec::new();
+        for (id, text) in [
+            (b"TIT2", b"\0Caf\xe9".to_vec()),
+            (b"TPE1", b"\x01\xff\xfeA\0\0\0\xff\xfeB\0".to_vec()),
+        ] {
+            frames.extend(id);
+            frames.extend(u32::try_from(text.len()).unwrap().to_be_bytes());
+            frames.extend([0, 0]);
+            frames.extend(text);
+        }
+        let mut bytes = b"ID3\x03\0\0\0\0\0".to_vec();
+        bytes.push(u8::try_from(frames.len() + 10).unwrap());
+        bytes.extend(frames);
+        bytes.extend([0; 10]);
+        bytes.extend(silent_mp3(false, 10));
+
+        let decoded = decode(&bytes).unwrap();
+        assert_eq!(decoded.samples.len(), 10 * FRAME_SAMPLES * 2);
+        assert_eq!(
+            decoded.tags,
+            [
+                ("TITLE".to_owned(), "Caf\u{e9}".to_owned()),
+                ("ARTIST".to_owned(), "A".to_owned()),
+                ("ARTIST".to_owned(), "B".to_owned()),
+            ]
+        );
+
+        // ID3v1 tags are read when there is no ID3v2 tag.
+        let mut bytes = silent_mp3(true, 10);
+        let mut tag = [0; 128];
+        tag[..3].copy_from_slice(b"TAG");
+        tag[3..8].copy_from_slice(b"Title");
+        tag[93..97].copy_from_slice(b"1999");
+        bytes.extend(tag);
+        assert_eq!(
+            decode(&bytes).unwrap().tags,
+            [
+                ("TITLE".to_owned(), "Title".to_owned()),
+                ("DATE".to_owned(), "1999".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_whole_frames() {
+        let bytes = silent_mp3(false, 10);
+        let mut frames = Mp3Frames::new(&bytes[..]);
+        assert_eq!(
+            frames.skip(3 * FRAME_SAMPLES as u64 - 1),
+            2 * FRAME_SAMPLES as u64
+        );
+        assert_eq!(frames.skip(FRAME_SAMPLES as u64), FRAME_SAMPLES as u64);
+        assert_eq!(
+            frames.next_frame().unwrap().samples.len(),
+            FRAME_SAMPLES * 2
+        );
+        assert_eq!(
+            frames.skip(100 * FRAME_SAMPLES as u64),
+            6 * FRAME_SAMPLES as u64
+        );
+        assert!(frames.next_frame().is_none());
+    }
+
+    #[test]
+    fn survives_corrupt_data() {
+        // Truncated files decode up to their last whole frame.
+        let bytes = silent_mp3(false, 10);
+        let decoded = decode(&bytes[..bytes.len() - 100]).unwrap();
+        assert_eq!(decoded.samples.len(), 9 * FRAME_SAMPLES * 2);
+
+        // Garbage is skipped, wherever it is.
+        let mut state = 0x2545_f491_u32;
+        let mut garbage = || {
+            (0..5000)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 17;
+                    state ^= state << 5;
+                    state.to_le_bytes()[0]
+                })
+                .collect::<Vec<u8>>()
+        };
+        let _ = decode(&garbage());
+        let mut bytes = garbage();
+        bytes.extend(silent_mp3(true, 4));
+        bytes.extend(garbage());
+        bytes.extend(silent_mp3(true, 4));
+        let decoded = decode(&bytes).unwrap();
+        assert!(decoded.samples.len() >= 4 * FRAME_SAMPLES);
+
+        // Headers with nothing after them are not decoded.
+        for len in 0..8 {
+            let _ = decode(&[0xff, 0xfb, 0x90, 0x04, 0xff, 0xfb, 0x90, 0x04][..len]);
+        }
+    }
+
+    #[test]
+    fn rejects_empty_files() {
+        assert!(matches!(
+            decode(&[]),
+            Err(AudioLoadError::CorruptData { offset: 0, .. })
+        ));
     }
 }
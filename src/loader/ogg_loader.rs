@@ -1,12 +1,67 @@
 use {
-    crate::AudioSource,
-    bevy::asset::{AssetLoader, BoxedFuture, Error, LoadContext, LoadedAsset},
-    lewton::inside_ogg::OggStreamReader,
-    std::io::Cursor,
+    super::{
+        channels::{set_audio_source, ChannelOrder},
+        AudioLoadError, Decoded, LoadOptions, Tracked,
+    },
+    crate::{cues::Cues, metadata::AudioFormat},
+    bevy::asset::{AssetLoader, BoxedFuture, Error, LoadContext},
+    lewton::{
+        header::HeaderReadError, inside_ogg::OggStreamReader, samples::InterleavedSamples,
+        VorbisError,
+    },
+    std::{
+        io::Cursor,
+        sync::atomic::{AtomicU64, Ordering},
+    },
 };
 
 #[derive(Default)]
-pub struct OggLoader;
+pub struct OggLoader {
+    pub(crate) options: LoadOptions,
+}
+
+pub(crate) fn ogg_error(err: VorbisError, offset: u64) -> AudioLoadError {
+    match err {
+        // Such as Ogg files holding Opus instead of Vorbis.
+        VorbisError::BadHeader(
+            HeaderReadError::NotVorbisHeader | HeaderReadError::UnsupportedVorbisVersion,
+        ) => AudioLoadError::UnsupportedFormat(err.to_string()),
+        err => AudioLoadError::CorruptData {
+            offset,
+            reason: err.to_string(),
+        },
+    }
+}
+
+fn decode(bytes: &[u8]) -> Result<Decoded, AudioLoadError> {
+    let position = AtomicU64::new(0);
+    let error = |err| ogg_error(err, position.load(Ordering::Relaxed));
+
+    let mut ogg_stream_reader = OggStreamReader::new(Tracked {
+        inner: Cursor::new(bytes),
+        position: &position,
+    })
+    .map_err(error)?;
+
+    let mut samples: Vec<f32> = Vec::new();
+
+    while let Some(packet) = ogg_stream_reader
+        .read_dec_packet_generic::<InterleavedSamples<f32>>()
+        .map_err(error)?
+    {
+        samples.extend(packet.samples);
+    }
+
+    Ok(Decoded {
+        sample_rate: ogg_stream_reader.ident_hdr.audio_sample_rate,
+        channels: usize::from(ogg_stream_reader.ident_hdr.audio_channels),
+        samples,
+        cues: Cues::default(),
+        format: AudioFormat::Ogg,
+        bits_per_sample: None,
+        tags: ogg_stream_reader.comment_hdr.comment_list,
+    })
+}
 
 impl AssetLoader for OggLoader {
     fn load<'a>(
@@ -15,41 +70,12 @@ impl AssetLoader for OggLoader {
         load_context: &'a mut LoadContext,
     ) -> BoxedFuture<'a, Result<(), Error>> {
         Box::pin(async move {
-            let mut ogg_stream_reader = OggStreamReader::new(Cursor::new(bytes))?;
-
-            let mut samples: Vec<Vec<f32>> = Vec::new();
-
-            while let Some(packets) =
-                ogg_stream_reader.read_dec_packet_generic::<Vec<Vec<f32>>>()?
-            {
-                samples.extend(packets);
-            }
-
-            let channels = ogg_stream_reader.ident_hdr.audio_channels;
-
-            match channels {
-                1 => {
-                    let frames = oddio::Frames::from_iter(
-                        ogg_stream_reader.ident_hdr.audio_sample_rate,
-                        samples.iter().map(|packet| [packet[0]]),
-                    );
-
-                    let audio_source = AudioSource { frames };
-
-                    load_context.set_default_asset(LoadedAsset::new(audio_source));
-                }
-                2 => {
-                    let frames = oddio::Frames::from_iter(
-                        ogg_stream_reader.ident_hdr.audio_sample_rate,
-                        samples.iter().map(|packet| [packet[0], packet[1]]),
-                    );
-
-                    let audio_source = AudioSource { frames };
-
-                    load_context.set_default_asset(LoadedAsset::new(audio_source));
-                }
-                _ => unimplemented!("bevy_oddio only have support for 1 or 2 channels only."),
-            }
+            set_audio_source(
+                load_context,
+                decode(bytes)?,
+                ChannelOrder::Vorbis,
+                &self.options,
+            )?;
 
             Ok(())
         })
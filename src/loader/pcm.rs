@@ -0,0 +1,61 @@
+//! Conversion of integer PCM samples to floating point.
+
+use oddio::Sample;
+
+/// Check that integer samples with the given bit depth can be converted.
+#[cfg(any(feature = "flac", feature = "wav"))]
+pub(crate) fn check_bit_depth(bits: u32) -> Result<(), super::AudioLoadError> {
+    if (1..=32).contains(&bits) {
+        Ok(())
+    } else {
+        Err(super::AudioLoadError::UnsupportedBitDepth(bits))
+    }
+}
+
+/// Convert a signed integer sample with the given bit depth to floating point.
+///
+/// Samples are scaled by `2^(bits - 1)`, so the most negative sample maps to
+/// exactly `-1.0`, and the most positive one to just under `1.0`, or to `1.0`
+/// itself once rounded at 32 bits.
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+pub(crate) fn int_to_sample(sample: i32, bits: u32) -> Sample {
+    debug_assert!((1..=32).contains(&bits), "unsupported bit depth {bits}");
+    (f64::from(sample) / (1_u64 << (bits - 1)) as f64) as Sample
+}
+
+/// Convert a 16-bit sample to floating point. See [`int_to_sample`].
+#[cfg(feature = "mp3")]
+pub(crate) fn i16_to_sample(sample: i16) -> Sample {
+    int_to_sample(i32::from(sample), 16)
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::int_to_sample;
+
+    #[test]
+    fn full_scale() {
+        for bits in [8, 16, 24, 32] {
+            let min = -(1_i64 << (bits - 1));
+            let max = (1_i64 << (bits - 1)) - 1;
+            let min = i32::try_from(min).unwrap();
+            let max = i32::try_from(max).unwrap();
+
+            assert_eq!(int_to_sample(min, bits), -1.0, "{bits}-bit minimum");
+            assert_eq!(int_to_sample(0, bits), 0.0, "{bits}-bit zero");
+            let top = int_to_sample(max, bits);
+            assert!(top > 0.99 && top <= 1.0, "{bits}-bit maximum is {top}");
+        }
+    }
+
+    #[test]
+    fn half_scale() {
+        assert_eq!(int_to_sample(64, 8), 0.5);
+        assert_eq!(int_to_sample(-16_384, 16), -0.5);
+        assert_eq!(int_to_sample(4_194_304, 24), 0.5);
+        assert_eq!(int_to_sample(-1_073_741_824, 32), -0.5);
+        #[cfg(feature = "mp3")]
+        assert_eq!(super::i16_to_sample(16_384), 0.5);
+    }
+}
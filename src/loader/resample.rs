@@ -0,0 +1,162 @@
+//! Windowed sinc resampling of decoded files.
+
+use {oddio::Sample, std::f64::consts::PI};
+
+/// Zero crossings of the sinc kernel on either side of each output sample,
+/// at the lower of the two sample rates.
+const ZERO_CROSSINGS: usize = 16;
+
+/// Most kernel phases precomputed, which covers the ratio between any two common sample rates.
+const MAX_PHASES: u64 = 4096;
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Weights of the kernel taps around an output sample that lies `fract` of
+/// the way from its base input sample to the next one.
+///
+/// The taps start `half - 1` input samples before the base sample.
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+fn kernel(fract: f64, cutoff: f64, half: usize, weights: &mut [f32]) {
+    let width = half as f64;
+    let mut sum = 0.0;
+    for (tap, weight) in weights.iter_mut().enumerate() {
+        let x = fract + width - 1.0 - tap as f64;
+        let sinc = if x == 0.0 {
+            1.0
+        } else {
+            (PI * cutoff * x).sin() / (PI * cutoff * x)
+        };
+        // Blackman window over the width of the kernel.
+        let position = x / width;
+        let window = if position.abs() >= 1.0 {
+            0.0
+        } else {
+            0.42 + 0.5 * (PI * position).cos() + 0.08 * (2.0 * PI * position).cos()
+        };
+        let value = sinc * window;
+        *weight = value as f32;
+        sum += value;
+    }
+    // Keep the gain at DC exactly one.
+    for weight in weights {
+        *weight /= sum as f32;
+    }
+}
+
+/// Resample interleaved samples from one sample rate to another.
+///
+/// When downsampling, content above the new Nyquist frequency is filtered out
+/// instead of aliasing.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_possible_wrap,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+pub(crate) fn resample(samples: &[Sample], channels: usize, from: u32, to: u32) -> Vec<Sample> {
+    if from == to || channels == 0 || from == 0 || to == 0 {
+        return samples.to_vec();
+    }
+
+    let divisor = gcd(u64::from(from), u64::from(to));
+    // Every output sample is `step / phases` input samples after the previous one.
+    let (step, phases) = (u64::from(from) / divisor, u64::from(to) / divisor);
+    let cutoff = (f64::from(to) / f64::from(from)).min(1.0);
+    let half = (ZERO_CROSSINGS as f64 / cutoff).ceil() as usize;
+    let taps = 2 * half;
+
+    let table = (phases <= MAX_PHASES).then(|| {
+        let mut table = vec![0.0; phases as usize * taps];
+        for (phase, weights) in table.chunks_exact_mut(taps).enumerate() {
+            kernel(phase as f64 / phases as f64, cutoff, half, weights);
+        }
+        table
+    });
+    let mut scratch = vec![0.0; taps];
+
+    let frames = (samples.len() / channels) as u64;
+    let output_frames = (frames * phases).div_ceil(step);
+    let mut output = Vec::with_capacity(output_frames as usize * channels);
+
+    for frame in 0..output_frames {
+        let position = frame * step;
+        let base = (position / phases) as usize;
+        let phase = position % phases;
+        let weights = if let Some(table) = &table {
+            &table[phase as usize * taps..(phase as usize + 1) * taps]
+        } else {
+            kernel(phase as f64 / phases as f64, cutoff, half, &mut scratch);
+            &scratch
+        };
+
+        let first = base as isize - (half as isize - 1);
+        for channel in 0..channels {
+            let mut sum = 0.0;
+            for (tap, weight) in weights.iter().enumerate() {
+                let index = first + tap as isize;
+                // Samples outside the file are silent.
+                if index >= 0 && (index as u64) < frames {
+                    sum += samples[index as usize * channels + channel] * weight;
+                }
+            }
+            output.push(sum);
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::resample, std::f32::consts::TAU};
+
+    fn sine(frequency: f32, sample_rate: u32, frames: usize) -> Vec<f32> {
+        #[allow(clippy::cast_precision_loss)]
+        (0..frames)
+            .map(|i| (TAU * frequency * i as f32 / sample_rate as f32).sin())
+            .collect()
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        #[allow(clippy::cast_precision_loss)]
+        let mean = samples.iter().map(|sample| sample * sample).sum::<f32>() / samples.len() as f32;
+        mean.sqrt()
+    }
+
+    #[test]
+    fn keeps_tones() {
+        let input = sine(1000.0, 44100, 44100);
+        let output = resample(&input, 1, 44100, 48000);
+        assert_eq!(output.len(), 48000);
+
+        // Skip the edges, where the kernel reaches past the file.
+        let expected = sine(1000.0, 48000, 48000);
+        for (output, expected) in output[100..47900].iter().zip(&expected[100..47900]) {
+            assert!((output - expected).abs() < 1e-3, "{output} != {expected}");
+        }
+    }
+
+    #[test]
+    fn keeps_channels_apart() {
+        let input: Vec<f32> = (0..1000).flat_map(|_| [0.5, -0.25]).collect();
+        let output = resample(&input, 2, 22050, 48000);
+        assert_eq!(output.len(), 2 * 2177);
+        for frame in output[200..4000].chunks_exact(2) {
+            assert!((frame[0] - 0.5).abs() < 1e-4);
+            assert!((frame[1] + 0.25).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn filters_above_nyquist() {
+        // 20 kHz can't be represented at 22.05 kHz, and must not alias down.
+        let input = sine(20000.0, 48000, 48000);
+        let output = resample(&input, 1, 48000, 22050);
+        assert!(rms(&output[100..22000]) < 0.01);
+    }
+}
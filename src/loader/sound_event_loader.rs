@@ -0,0 +1,113 @@
+use {
+    crate::{
+        random::Selection,
+        sound_event::{SoundEvent, SoundEventSettings, SpatialSoundSettings},
+    },
+    bevy::asset::{AssetLoader, AssetPath, BoxedFuture, Error, LoadContext, LoadedAsset},
+    oddio::Sample,
+    serde::Deserialize,
+    std::path::Path,
+};
+
+/// The contents of a `*.sound.ron` file.
+#[derive(Deserialize)]
+struct SoundEventDescriptor {
+    sounds: Vec<String>,
+    #[serde(default)]
+    bus: Option<String>,
+    #[serde(default = "one")]
+    volume: f32,
+    #[serde(default)]
+    volume_variation: f32,
+    #[serde(default = "one")]
+    pitch: f32,
+    #[serde(default)]
+    pitch_variation: f32,
+    #[serde(default)]
+    selection: Selection,
+    #[serde(default)]
+    looping: bool,
+    #[serde(default)]
+    spatial: Option<SpatialSoundSettings>,
+    #[serde(default)]
+    polyphony: Option<usize>,
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+#[derive(Default)]
+pub struct SoundEventLoader;
+
+impl AssetLoader for SoundEventLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            let descriptor: SoundEventDescriptor = ron::de::from_bytes(bytes)?;
+
+            let parent = load_context
+                .path()
+                .parent()
+                .unwrap_or_else(|| Path::new(""));
+            let paths: Vec<AssetPath<'static>> = descriptor
+                .sounds
+                .iter()
+                .map(|sound| AssetPath::new(parent.join(sound), None))
+                .collect();
+            let sounds = paths
+                .iter()
+                .map(|path| load_context.get_handle_untyped(path.clone()))
+                .collect();
+
+            let settings = SoundEventSettings {
+                bus: descriptor.bus,
+                volume: descriptor.volume,
+                volume_variation: descriptor.volume_variation,
+                pitch: descriptor.pitch,
+                pitch_variation: descriptor.pitch_variation,
+                selection: descriptor.selection,
+                looping: descriptor.looping,
+                spatial: descriptor.spatial,
+                polyphony: descriptor.polyphony,
+            };
+
+            // Spatial events are played in the spatial scene, which only accepts mono signals.
+            if settings.spatial.is_some() {
+                let event = SoundEvent::<Sample>::new(sounds, settings);
+                load_context.set_default_asset(LoadedAsset::new(event).with_dependencies(paths));
+            } else {
+                let event = SoundEvent::<[Sample; 2]>::new(sounds, settings);
+                load_context.set_default_asset(LoadedAsset::new(event).with_dependencies(paths));
+            }
+
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sound.ron"]
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use {super::SoundEventDescriptor, crate::random::Selection};
+
+    #[test]
+    fn defaults() {
+        let descriptor: SoundEventDescriptor =
+            ron::from_str(r#"(sounds: ["a.wav"], pitch_variation: 0.1, selection: Sequential)"#)
+                .unwrap();
+        assert_eq!(descriptor.sounds, ["a.wav"]);
+        assert_eq!(descriptor.volume, 1.0);
+        assert_eq!(descriptor.pitch, 1.0);
+        assert_eq!(descriptor.pitch_variation, 0.1);
+        assert_eq!(descriptor.selection, Selection::Sequential);
+        assert!(descriptor.spatial.is_none());
+    }
+}
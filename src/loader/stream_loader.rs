@@ -0,0 +1,557 @@
+#[cfg(any(feature = "flac", feature = "ogg", feature = "wav"))]
+use {
+    super::Tracked,
+    std::{
+        io::Cursor,
+        sync::atomic::{AtomicU64, Ordering},
+    },
+};
+use {
+    super::{
+        channels::{prepare, ChannelOrder, MAX_CHANNELS},
+        AudioLoadError,
+    },
+    crate::{
+        stream::{StreamFormat, StreamingAudioSource},
+        Downmix,
+    },
+    bevy::asset::{AssetLoader, BoxedFuture, Error, LoadContext, LoadedAsset},
+    oddio::Sample,
+    std::sync::Arc,
+};
+
+#[cfg(feature = "flac")]
+use super::flac_loader::flac_error;
+#[cfg(feature = "mp3")]
+use super::mp3_loader::Mp3Frames;
+#[cfg(feature = "ogg")]
+use super::ogg_loader::ogg_error;
+#[cfg(feature = "mp3")]
+use super::pcm::i16_to_sample;
+#[cfg(any(feature = "flac", feature = "wav"))]
+use super::pcm::{check_bit_depth, int_to_sample};
+#[cfg(feature = "wav")]
+use super::wav_loader::wav_error;
+
+/// Number of samples decoded at once, for formats that don't decode in blocks of their own.
+#[cfg(feature = "wav")]
+const BLOCK_SAMPLES: usize = 4096;
+
+#[derive(Default)]
+pub struct StreamLoader {
+    pub(crate) downmix: Downmix,
+}
+
+impl AssetLoader for StreamLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            let extension = load_context
+                .path()
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .unwrap_or_default();
+            let format = match extension {
+                #[cfg(feature = "flac")]
+                "flac" => StreamFormat::Flac,
+                #[cfg(feature = "mp3")]
+                "mp3" => StreamFormat::Mp3,
+                #[cfg(feature = "ogg")]
+                "ogg" => StreamFormat::Ogg,
+                #[cfg(feature = "wav")]
+                "wav" => StreamFormat::Wav,
+                _ => {
+                    return Err(AudioLoadError::UnsupportedFormat(format!(
+                        "streaming .{extension}"
+                    ))
+                    .into())
+                }
+            };
+
+            let (sample_rate, channels) = probe(format, bytes)?;
+            let bytes: Arc<[u8]> = bytes.into();
+
+            match (channels, self.downmix) {
+                (1, _) | (2..=MAX_CHANNELS, Downmix::Mono) => {
+                    load_context.set_default_asset(LoadedAsset::new(StreamingAudioSource::<
+                        [Sample; 1],
+                    >::new(
+                        bytes,
+                        format,
+                        sample_rate,
+                        channels,
+                    )));
+                }
+                (2, _) | (3..=MAX_CHANNELS, Downmix::Stereo) => {
+                    load_context.set_default_asset(LoadedAsset::new(StreamingAudioSource::<
+                        [Sample; 2],
+                    >::new(
+                        bytes,
+                        format,
+                        sample_rate,
+                        channels,
+                    )));
+                }
+                _ => return Err(AudioLoadError::UnsupportedChannelCount(channels).into()),
+            }
+
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[
+            #[cfg(feature = "flac")]
+            "stream.flac",
+            #[cfg(feature = "mp3")]
+            "stream.mp3",
+            #[cfg(feature = "ogg")]
+            "stream.ogg",
+            #[cfg(feature = "wav")]
+            "stream.wav",
+        ]
+    }
+}
+
+/// Read the sample rate and number of channels of an encoded file.
+fn probe(format: StreamFormat, bytes: &[u8]) -> Result<(u32, usize), AudioLoadError> {
+    match format {
+        #[cfg(feature = "flac")]
+        StreamFormat::Flac => {
+            let streaminfo = claxon::FlacReader::new(bytes)
+                .map_err(|err| flac_error(err, 0))?
+                .streaminfo();
+            check_bit_depth(streaminfo.bits_per_sample)?;
+            Ok((streaminfo.sample_rate, streaminfo.channels as usize))
+        }
+        #[cfg(feature = "mp3")]
+        StreamFormat::Mp3 => Mp3Frames::new(bytes)
+            .next_frame()
+            .map(|frame| (frame.sample_rate, frame.channels))
+            .ok_or(AudioLoadError::CorruptData {
+                offset: 0,
+                reason: "no MP3 frames found".into(),
+            }),
+        #[cfg(feature = "ogg")]
+        StreamFormat::Ogg => {
+            let reader = lewton::inside_ogg::OggStreamReader::new(std::io::Cursor::new(bytes))
+                .map_err(|err| ogg_error(err, 0))?;
+            Ok((
+                reader.ident_hdr.audio_sample_rate,
+                usize::from(reader.ident_hdr.audio_channels),
+            ))
+        }
+        #[cfg(feature = "wav")]
+        StreamFormat::Wav => {
+            let spec = hound::WavReader::new(bytes)
+                .map_err(|err| wav_error(err, 0))?
+                .spec();
+            let bits_per_sample = u32::from(spec.bits_per_sample);
+            match spec.sample_format {
+                hound::SampleFormat::Int => check_bit_depth(bits_per_sample)?,
+                hound::SampleFormat::Float if bits_per_sample == 32 => {}
+                hound::SampleFormat::Float => {
+                    return Err(AudioLoadError::UnsupportedBitDepth(bits_per_sample))
+                }
+            }
+            Ok((spec.sample_rate, usize::from(spec.channels)))
+        }
+    }
+}
+
+/// Decodes a file a block of interleaved samples at a time, for [`StreamDecoder`].
+trait BlockDecoder {
+    /// Decode the next block into `samples`, replacing what they held.
+    ///
+    /// Returns `false` once the end of the file is reached.
+    fn next_block(&mut self, samples: &mut Vec<Sample>) -> Result<bool, AudioLoadError>;
+
+    /// Continue decoding from `frame`, or from the closest frame before it that the
+    /// format can seek to, or from the end of the file if it is shorter.
+    ///
+    /// Returns the frame that decoding continues from.
+    fn seek(&mut self, frame: u64) -> Result<u64, AudioLoadError>;
+}
+
+/// Decodes a [`StreamingAudioSource`] while it plays.
+pub(crate) struct StreamDecoder {
+    blocks: Box<dyn BlockDecoder + Send>,
+    channels: usize,
+    order: ChannelOrder,
+    downmix: Downmix,
+}
+
+impl StreamDecoder {
+    /// Start decoding `bytes` from the start, downmixing them to at most `max_channels`
+    /// as with [`Downmix`].
+    pub(crate) fn new(
+        format: StreamFormat,
+        bytes: Arc<[u8]>,
+        max_channels: usize,
+    ) -> Result<Self, AudioLoadError> {
+        let (blocks, channels, order): (Box<dyn BlockDecoder + Send>, _, _) = match format {
+            #[cfg(feature = "flac")]
+            StreamFormat::Flac => {
+                let blocks = FlacBlocks::new(bytes)?;
+                let channels = blocks.reader.streaminfo().channels as usize;
+                (Box::new(blocks), channels, ChannelOrder::Wave)
+            }
+            #[cfg(feature = "mp3")]
+            StreamFormat::Mp3 => {
+                let blocks = Mp3Blocks::new(bytes)?;
+                let channels = blocks.channels;
+                (Box::new(blocks), channels, ChannelOrder::Wave)
+            }
+            #[cfg(feature = "ogg")]
+            StreamFormat::Ogg => {
+                let blocks = OggBlocks::new(bytes)?;
+                let channels = usize::from(blocks.reader.ident_hdr.audio_channels);
+                (Box::new(blocks), channels, ChannelOrder::Vorbis)
+            }
+            #[cfg(feature = "wav")]
+            StreamFormat::Wav => {
+                let blocks = WavBlocks::new(bytes)?;
+                let channels = usize::from(blocks.reader.spec().channels);
+                (Box::new(blocks), channels, ChannelOrder::Wave)
+            }
+        };
+
+        Ok(Self {
+            blocks,
+            channels,
+            order,
+            downmix: if max_channels == 1 {
+                Downmix::Mono
+            } else {
+                Downmix::Stereo
+            },
+        })
+    }
+
+    /// Decode the next block of interleaved samples into `samples`.
+    ///
+    /// Returns the number of channels of the block, or `None` at the end of the file.
+    pub(crate) fn next_block(
+        &mut self,
+        samples: &mut Vec<Sample>,
+    ) -> Result<Option<usize>, AudioLoadError> {
+        if !self.blocks.next_block(samples)? {
+            return Ok(None);
+        }
+        prepare(samples, self.channels, self.order, self.downmix).map(Some)
+    }
+
+    /// Continue decoding from `frame`, or from a frame before it.
+    ///
+    /// Returns the frame that decoding continues from. See [`BlockDecoder::seek`].
+    pub(crate) fn seek(&mut self, frame: u64) -> Result<u64, AudioLoadError> {
+        self.blocks.seek(frame)
+    }
+}
+
+/// Reader of a file for a [`BlockDecoder`], tracking how far it has read.
+#[cfg(any(feature = "flac", feature = "ogg", feature = "wav"))]
+type StreamReader = Tracked<Cursor<Arc<[u8]>>, Arc<AtomicU64>>;
+
+#[cfg(any(feature = "flac", feature = "ogg", feature = "wav"))]
+fn stream_reader(bytes: Arc<[u8]>) -> (StreamReader, Arc<AtomicU64>) {
+    let position = Arc::new(AtomicU64::new(0));
+    let reader = Tracked {
+        inner: Cursor::new(bytes),
+        position: position.clone(),
+    };
+    (reader, position)
+}
+
+/// FLAC files can't be seeked in, so seeking backwards decodes them from the start again.
+#[cfg(feature = "flac")]
+struct FlacBlocks {
+    bytes: Arc<[u8]>,
+    reader: claxon::FlacReader<StreamReader>,
+    position: Arc<AtomicU64>,
+    buffer: Vec<i32>,
+    /// A block decoded while seeking, to be returned next.
+    pending: Vec<Sample>,
+    /// Frame that the next block starts at.
+    frame: u64,
+}
+
+#[cfg(feature = "flac")]
+impl FlacBlocks {
+    fn new(bytes: Arc<[u8]>) -> Result<Self, AudioLoadError> {
+        let (reader, position) = stream_reader(bytes.clone());
+        let reader = claxon::FlacReader::new(reader)
+            .map_err(|err| flac_error(err, position.load(Ordering::Relaxed)))?;
+        Ok(Self {
+            bytes,
+            reader,
+            position,
+            buffer: Vec::new(),
+            pending: Vec::new(),
+            frame: 0,
+        })
+    }
+
+    /// Decode the next block into `samples`, without counting it as played.
+    fn read(&mut self, samples: &mut Vec<Sample>) -> Result<bool, AudioLoadError> {
+        let bits_per_sample = self.reader.streaminfo().bits_per_sample;
+        let block = self
+            .reader
+            .blocks()
+            .read_next_or_eof(std::mem::take(&mut self.buffer))
+            .map_err(|err| flac_error(err, self.position.load(Ordering::Relaxed)))?;
+        let Some(block) = block else {
+            return Ok(false);
+        };
+
+        samples.clear();
+        for i in 0..block.duration() {
+            for channel in 0..block.channels() {
+                samples.push(int_to_sample(block.sample(channel, i), bits_per_sample));
+            }
+        }
+        self.buffer = block.into_buffer();
+        Ok(true)
+    }
+}
+
+#[cfg(feature = "flac")]
+impl BlockDecoder for FlacBlocks {
+    fn next_block(&mut self, samples: &mut Vec<Sample>) -> Result<bool, AudioLoadError> {
+        if self.pending.is_empty() {
+            if !self.read(samples)? {
+                return Ok(false);
+            }
+        } else {
+            std::mem::swap(samples, &mut self.pending);
+            self.pending.clear();
+        }
+        let channels = self.reader.streaminfo().channels as usize;
+        self.frame += (samples.len() / channels) as u64;
+        Ok(true)
+    }
+
+    fn seek(&mut self, frame: u64) -> Result<u64, AudioLoadError> {
+        if frame < self.frame {
+            *self = Self::new(self.bytes.clone())?;
+        }
+        let channels = self.reader.streaminfo().channels as usize;
+        let mut pending = std::mem::take(&mut self.pending);
+        loop {
+            if pending.is_empty() && !self.read(&mut pending)? {
+                break;
+            }
+            let frames = (pending.len() / channels) as u64;
+            if self.frame + frames > frame {
+                break;
+            }
+            self.frame += frames;
+            pending.clear();
+        }
+        self.pending = pending;
+        Ok(self.frame)
+    }
+}
+
+/// MP3 files are seeked in by skipping frames without decoding them.
+#[cfg(feature = "mp3")]
+struct Mp3Blocks {
+    bytes: Arc<[u8]>,
+    frames: Mp3Frames<Arc<[u8]>>,
+    channels: usize,
+    /// Frame that the next block starts at.
+    frame: u64,
+}
+
+#[cfg(feature = "mp3")]
+impl Mp3Blocks {
+    fn new(bytes: Arc<[u8]>) -> Result<Self, AudioLoadError> {
+        let channels = Mp3Frames::new(&bytes[..])
+            .next_frame()
+            .ok_or(AudioLoadError::CorruptData {
+                offset: 0,
+                reason: "no MP3 frames found".into(),
+            })?
+            .channels;
+        Ok(Self {
+            frames: Mp3Frames::new(bytes.clone()),
+            bytes,
+            channels,
+            frame: 0,
+        })
+    }
+}
+
+#[cfg(feature = "mp3")]
+impl BlockDecoder for Mp3Blocks {
+    fn next_block(&mut self, samples: &mut Vec<Sample>) -> Result<bool, AudioLoadError> {
+        let offset = self.frames.offset();
+        let Some(frame) = self.frames.next_frame() else {
+            return Ok(false);
+        };
+        if frame.channels != self.channels {
+            return Err(AudioLoadError::CorruptData {
+                offset,
+                reason: format!(
+                    "MP3 changes from {} to {} channels mid-stream",
+                    self.channels, frame.channels
+                ),
+            });
+        }
+
+        samples.clear();
+        samples.extend(frame.samples.iter().copied().map(i16_to_sample));
+        self.frame += (samples.len() / self.channels) as u64;
+        Ok(true)
+    }
+
+    fn seek(&mut self, frame: u64) -> Result<u64, AudioLoadError> {
+        if frame < self.frame {
+            self.frames = Mp3Frames::new(self.bytes.clone());
+            self.frame = 0;
+        }
+        self.frame += self.frames.skip(frame - self.frame);
+        Ok(self.frame)
+    }
+}
+
+/// Ogg Vorbis files are seeked in a page at a time.
+#[cfg(feature = "ogg")]
+struct OggBlocks {
+    bytes: Arc<[u8]>,
+    reader: lewton::inside_ogg::OggStreamReader<StreamReader>,
+    position: Arc<AtomicU64>,
+    /// Packets decoded while seeking, to be returned next.
+    pending: Vec<Sample>,
+    /// Whether nothing has been decoded yet.
+    at_start: bool,
+}
+
+#[cfg(feature = "ogg")]
+impl OggBlocks {
+    fn new(bytes: Arc<[u8]>) -> Result<Self, AudioLoadError> {
+        let (reader, position) = stream_reader(bytes.clone());
+        let reader = lewton::inside_ogg::OggStreamReader::new(reader)
+            .map_err(|err| ogg_error(err, position.load(Ordering::Relaxed)))?;
+        Ok(Self {
+            bytes,
+            reader,
+            position,
+            pending: Vec::new(),
+            at_start: true,
+        })
+    }
+
+    /// Decode the next packet, returning `None` at the end of the file.
+    fn read(&mut self) -> Result<Option<Vec<Sample>>, AudioLoadError> {
+        self.at_start = false;
+        let packet = self
+            .reader
+            .read_dec_packet_generic::<lewton::samples::InterleavedSamples<f32>>()
+            .map_err(|err| ogg_error(err, self.position.load(Ordering::Relaxed)))?;
+        Ok(packet.map(|packet| packet.samples))
+    }
+}
+
+#[cfg(feature = "ogg")]
+impl BlockDecoder for OggBlocks {
+    fn next_block(&mut self, samples: &mut Vec<Sample>) -> Result<bool, AudioLoadError> {
+        if !self.pending.is_empty() {
+            std::mem::swap(samples, &mut self.pending);
+            self.pending.clear();
+            return Ok(true);
+        }
+        // The first packet, and the first one after a seek, decode to nothing.
+        loop {
+            match self.read()? {
+                Some(packet) if packet.is_empty() => {}
+                Some(packet) => {
+                    *samples = packet;
+                    return Ok(true);
+                }
+                None => return Ok(false),
+            }
+        }
+    }
+
+    fn seek(&mut self, frame: u64) -> Result<u64, AudioLoadError> {
+        if frame == 0 {
+            if !self.at_start {
+                *self = Self::new(self.bytes.clone())?;
+            }
+            return Ok(0);
+        }
+
+        self.reader
+            .seek_absgp_pg(frame)
+            .map_err(|err| ogg_error(err, self.position.load(Ordering::Relaxed)))?;
+        // The position is only known at the end of a page, from its granule position.
+        let channels = usize::from(self.reader.ident_hdr.audio_channels);
+        self.pending.clear();
+        while let Some(packet) = self.read()? {
+            self.pending.extend(packet);
+            if let Some(granule) = self.reader.get_last_absgp() {
+                return Ok(granule.saturating_sub((self.pending.len() / channels) as u64));
+            }
+        }
+        Ok(frame)
+    }
+}
+
+/// WAV files are seeked in directly.
+#[cfg(feature = "wav")]
+struct WavBlocks {
+    reader: hound::WavReader<StreamReader>,
+    position: Arc<AtomicU64>,
+}
+
+#[cfg(feature = "wav")]
+impl WavBlocks {
+    fn new(bytes: Arc<[u8]>) -> Result<Self, AudioLoadError> {
+        let (reader, position) = stream_reader(bytes);
+        let reader = hound::WavReader::new(reader)
+            .map_err(|err| wav_error(err, position.load(Ordering::Relaxed)))?;
+        Ok(Self { reader, position })
+    }
+}
+
+#[cfg(feature = "wav")]
+impl BlockDecoder for WavBlocks {
+    fn next_block(&mut self, samples: &mut Vec<Sample>) -> Result<bool, AudioLoadError> {
+        let error = |err| wav_error(err, self.position.load(Ordering::Relaxed));
+        let spec = self.reader.spec();
+        // Keep whole frames in every block.
+        let block = BLOCK_SAMPLES - BLOCK_SAMPLES % usize::from(spec.channels.max(1));
+
+        samples.clear();
+        match spec.sample_format {
+            hound::SampleFormat::Int => {
+                let bits_per_sample = u32::from(spec.bits_per_sample);
+                for sample in self.reader.samples::<i32>().take(block) {
+                    samples.push(int_to_sample(sample.map_err(error)?, bits_per_sample));
+                }
+            }
+            hound::SampleFormat::Float => {
+                for sample in self.reader.samples::<f32>().take(block) {
+                    samples.push(sample.map_err(error)?);
+                }
+            }
+        }
+        Ok(!samples.is_empty())
+    }
+
+    fn seek(&mut self, frame: u64) -> Result<u64, AudioLoadError> {
+        let duration = self.reader.duration();
+        let frame = u32::try_from(frame).map_or(duration, |frame| frame.min(duration));
+        self.reader.seek(frame).map_err(|err| {
+            wav_error(
+                hound::Error::IoError(err),
+                self.position.load(Ordering::Relaxed),
+            )
+        })?;
+        Ok(u64::from(frame))
+    }
+}
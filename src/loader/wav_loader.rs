@@ -1,69 +1,361 @@
 use {
-    crate::AudioSource,
-    bevy::asset::{AssetLoader, BoxedFuture, Error, LoadContext, LoadedAsset},
+    super::{
+        channels::{set_audio_source, ChannelOrder},
+        pcm::{check_bit_depth, int_to_sample},
+        AudioLoadError, Decoded, LoadOptions, Tracked,
+    },
+    crate::{
+        cues::{Cues, Marker},
+        metadata::AudioFormat,
+    },
+    bevy::{
+        asset::{AssetLoader, BoxedFuture, Error, LoadContext},
+        utils::HashMap,
+    },
+    std::sync::atomic::{AtomicU64, Ordering},
 };
 
 #[derive(Default)]
-pub struct WavLoader;
+pub struct WavLoader {
+    pub(crate) options: LoadOptions,
+}
+
+pub(crate) fn wav_error(err: hound::Error, offset: u64) -> AudioLoadError {
+    match err {
+        hound::Error::Unsupported => {
+            AudioLoadError::UnsupportedFormat("WAV encoding other than PCM or IEEE float".into())
+        }
+        hound::Error::FormatError(reason) => AudioLoadError::CorruptData {
+            offset,
+            reason: reason.into(),
+        },
+        err => AudioLoadError::CorruptData {
+            offset,
+            reason: err.to_string(),
+        },
+    }
+}
+
+/// The chunks of a RIFF list, as pairs of chunk IDs and their data.
+///
+/// Stops at the first chunk that runs past the end of `bytes`.
+fn chunks(mut bytes: &[u8]) -> impl Iterator<Item = (&[u8], &[u8])> {
+    std::iter::from_fn(move || {
+        let id = bytes.get(..4)?;
+        let size = usize::try_from(read_u32(bytes, 4)?).ok()?;
+        let data = bytes.get(8..8usize.checked_add(size)?)?;
+        // Chunks are padded to an even size.
+        bytes = bytes.get(8 + size + size % 2..).unwrap_or_default();
+        Some((id, data))
+    })
+}
+
+/// The chunks of a RIFF file, or none if `bytes` isn't one.
+fn riff_chunks(bytes: &[u8]) -> impl Iterator<Item = (&[u8], &[u8])> {
+    let riff = bytes
+        .get(12..)
+        .filter(|_| bytes.starts_with(b"RIFF"))
+        .unwrap_or_default();
+    chunks(riff)
+}
+
+/// Text up to its null terminator.
+fn text(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&c| c == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let bytes = bytes.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/// Read the loop points of the `smpl` chunk, and the markers of the `cue ` chunk
+/// with their labels from the `adtl` list.
+///
+/// Chunks that are malformed are skipped, rather than failing the load.
+fn cues(bytes: &[u8]) -> Cues {
+    let mut cues = Cues::default();
+    let mut labels = HashMap::new();
+
+    for (id, data) in riff_chunks(bytes) {
+        match id {
+            // The first loop follows a header of 36 bytes, and starts with its cue point ID and type.
+            // Only forward loops are supported.
+            b"smpl"
+                if read_u32(data, 28).unwrap_or_default() > 0 && read_u32(data, 40) == Some(0) =>
+            {
+                if let (Some(start), Some(end)) = (read_u32(data, 44), read_u32(data, 48)) {
+                    // The end of the loop is the last frame played, not the one after it.
+                    cues.loop_region = Some(start as usize..end as usize + 1);
+                }
+            }
+            b"cue " => {
+                let count = read_u32(data, 0).unwrap_or_default() as usize;
+                cues.markers = data
+                    .get(4..)
+                    .unwrap_or_default()
+                    .chunks_exact(24)
+                    .take(count)
+                    .filter_map(|point| {
+                        Some(Marker {
+                            id: read_u32(point, 0)?,
+                            name: String::new(),
+                            frame: read_u32(point, 20)? as usize,
+                        })
+                    })
+                    .collect();
+            }
+            b"LIST" if data.starts_with(b"adtl") => {
+                for (id, data) in chunks(&data[4..]) {
+                    if id == b"labl" {
+                        if let Some(cue) = read_u32(data, 0) {
+                            labels.insert(cue, text(&data[4..]));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for marker in &mut cues.markers {
+        if let Some(label) = labels.remove(&marker.id) {
+            marker.name = label;
+        }
+    }
+    cues.markers.sort_by_key(|marker| marker.frame);
+    cues
+}
+
+/// Read the tags of the `INFO` list.
+fn tags(bytes: &[u8]) -> Vec<(String, String)> {
+    riff_chunks(bytes)
+        .filter(|(id, data)| id == b"LIST" && data.starts_with(b"INFO"))
+        .flat_map(|(_, data)| chunks(&data[4..]))
+        .map(|(id, data)| {
+            let key = match id {
+                b"INAM" => "TITLE".into(),
+                b"IART" => "ARTIST".into(),
+                b"IPRD" => "ALBUM".into(),
+                b"ICMT" => "COMMENT".into(),
+                b"ICRD" => "DATE".into(),
+                b"IGNR" => "GENRE".into(),
+                b"ITRK" | b"IPRT" => "TRACKNUMBER".into(),
+                b"ICOP" => "COPYRIGHT".into(),
+                b"ISFT" => "ENCODER".into(),
+                id => String::from_utf8_lossy(id).into_owned(),
+            };
+            (key, text(data))
+        })
+        .collect()
+}
 
 // Adapted from https://github.com/Ralith/oddio/blob/main/examples/wav.rs
+fn decode(bytes: &[u8]) -> Result<Decoded, AudioLoadError> {
+    let position = AtomicU64::new(0);
+    let error = |err| wav_error(err, position.load(Ordering::Relaxed));
+
+    let mut reader = hound::WavReader::new(Tracked {
+        inner: bytes,
+        position: &position,
+    })
+    .map_err(error)?;
+    let hound::WavSpec {
+        sample_rate,
+        sample_format,
+        bits_per_sample,
+        channels,
+    } = reader.spec();
+    let bits_per_sample = u32::from(bits_per_sample);
+
+    // convert the WAV data to floating point samples
+    // e.g. i8 data is converted from [-128, 127] to [-1.0, 1.0)
+    let samples_result: Result<Vec<f32>, _> = match sample_format {
+        hound::SampleFormat::Int => {
+            check_bit_depth(bits_per_sample)?;
+            reader
+                .samples::<i32>()
+                .map(|sample| sample.map(|sample| int_to_sample(sample, bits_per_sample)))
+                .collect()
+        }
+        hound::SampleFormat::Float if bits_per_sample == 32 => reader.samples::<f32>().collect(),
+        hound::SampleFormat::Float => {
+            return Err(AudioLoadError::UnsupportedBitDepth(bits_per_sample))
+        }
+    };
+
+    Ok(Decoded {
+        sample_rate,
+        channels: usize::from(channels),
+        samples: samples_result.map_err(error)?,
+        cues: cues(bytes),
+        format: AudioFormat::Wav,
+        bits_per_sample: Some(bits_per_sample),
+        tags: tags(bytes),
+    })
+}
+
 impl AssetLoader for WavLoader {
     fn load<'a>(
         &'a self,
         bytes: &'a [u8],
         load_context: &'a mut LoadContext,
     ) -> BoxedFuture<'a, Result<(), Error>> {
-        #[allow(clippy::cast_lossless, clippy::cast_precision_loss)]
         Box::pin(async move {
-            let mut reader = hound::WavReader::new(bytes)?;
-            let hound::WavSpec {
-                sample_rate: source_sample_rate,
-                sample_format,
-                bits_per_sample,
-                channels,
-            } = reader.spec();
-
-            // convert the WAV data to floating point samples
-            // e.g. i8 data is converted from [-128, 127] to [-1.0, 1.0]
-            let samples_result: Result<Vec<f32>, _> = match sample_format {
-                hound::SampleFormat::Int => {
-                    let max_value = 2_u32.pow(bits_per_sample as u32 - 1) - 1;
-                    reader
-                        .samples::<i32>()
-                        .map(|sample| sample.map(|sample| sample as f32 / max_value as f32))
-                        .collect()
-                }
-                hound::SampleFormat::Float => reader.samples::<f32>().collect(),
-            };
-            let mut samples = samples_result?;
+            // channels are interleaved, so we put them together in frames
+            set_audio_source(
+                load_context,
+                decode(bytes)?,
+                ChannelOrder::Wave,
+                &self.options,
+            )?;
 
-            match channels {
-                1 => {
-                    let samples = samples.into_iter().map(|s| [s]);
+            Ok(())
+        })
+    }
 
-                    let frames = oddio::Frames::from_iter(source_sample_rate, samples);
+    fn extensions(&self) -> &[&str] {
+        &["wav"]
+    }
+}
 
-                    let audio_source = AudioSource { frames };
+#[cfg(test)]
+mod tests {
+    use {
+        super::decode,
+        crate::{
+            cues::{Cues, Marker},
+            loader::AudioLoadError,
+        },
+        std::io::Cursor,
+    };
 
-                    load_context.set_default_asset(LoadedAsset::new(audio_source));
-                }
-                2 => {
-                    // channels are interleaved, so we put them together in stereo
-                    let samples_stereo = oddio::frame_stereo(&mut samples).iter().copied();
-                    let frames = oddio::Frames::from_iter(source_sample_rate, samples_stereo);
+    fn wav(spec: hound::WavSpec, frames: usize) -> Vec<u8> {
+        let mut bytes = Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut bytes, spec).unwrap();
+        for _ in 0..frames * usize::from(spec.channels) {
+            writer.write_sample(0_i16).unwrap();
+        }
+        writer.finalize().unwrap();
+        bytes.into_inner()
+    }
 
-                    let audio_source = AudioSource { frames };
+    #[test]
+    fn reports_error_kinds() {
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 44100,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let bytes = wav(spec, 100);
+        let decoded = decode(&bytes).unwrap();
+        assert_eq!(decoded.channels, 2);
+        assert_eq!(decoded.samples.len(), 200);
 
-                    load_context.set_default_asset(LoadedAsset::new(audio_source));
-                }
-                _ => unimplemented!("bevy_oddio only have support for 1 or 2 channels only."),
-            }
+        assert!(matches!(
+            decode(b"RIFX, not a WAV file"),
+            Err(AudioLoadError::CorruptData { .. })
+        ));
+        // Cutting the file off in the middle of a sample.
+        assert!(matches!(
+            decode(&bytes[..bytes.len() - 1]),
+            Err(AudioLoadError::CorruptData { offset, .. }) if offset > 44
+        ));
+    }
 
-            Ok(())
-        })
+    fn chunk(id: [u8; 4], fields: &[u32], text: &[u8]) -> Vec<u8> {
+        let mut data: Vec<u8> = fields
+            .iter()
+            .flat_map(|field| field.to_le_bytes())
+            .collect();
+        data.extend_from_slice(text);
+        let mut chunk = id.to_vec();
+        chunk.extend_from_slice(&u32::try_from(data.len()).unwrap().to_le_bytes());
+        chunk.extend_from_slice(&data);
+        if data.len() % 2 == 1 {
+            chunk.push(0);
+        }
+        chunk
     }
 
-    fn extensions(&self) -> &[&str] {
-        &["wav"]
+    #[test]
+    fn reads_cues() {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 44100,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut bytes = wav(spec, 1000);
+        // A forward loop over frames 100 to 899.
+        bytes.extend(chunk(
+            *b"smpl",
+            &[0, 0, 22675, 60, 0, 0, 0, 1, 0, 1, 0, 100, 899, 0, 0],
+            b"",
+        ));
+        bytes.extend(chunk(
+            *b"cue ",
+            &[2, 1, 0, 0x6174_6164, 0, 0, 700, 2, 0, 0x6174_6164, 0, 0, 50],
+            b"",
+        ));
+        let mut adtl = b"adtl".to_vec();
+        adtl.extend(chunk(*b"labl", &[1], b"chorus\0"));
+        adtl.extend(chunk(*b"labl", &[2], b"intro\0"));
+        bytes.extend(chunk(*b"LIST", &[], &adtl));
+        let riff_size = u32::try_from(bytes.len() - 8).unwrap();
+        bytes[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+        let decoded = decode(&bytes).unwrap();
+        assert_eq!(decoded.samples.len(), 1000);
+        assert_eq!(decoded.cues.loop_region, Some(100..900));
+        assert_eq!(
+            decoded.cues.markers,
+            [
+                Marker {
+                    id: 2,
+                    name: "intro".into(),
+                    frame: 50
+                },
+                Marker {
+                    id: 1,
+                    name: "chorus".into(),
+                    frame: 700
+                },
+            ]
+        );
+        assert_eq!(
+            decoded.cues.marker("chorus").map(|marker| marker.frame),
+            Some(700)
+        );
+
+        // Files without them have none.
+        assert_eq!(decode(&wav(spec, 10)).unwrap().cues, Cues::default());
+    }
+
+    #[test]
+    fn reads_info_tags() {
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 48000,
+            bits_per_sample: 24,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut bytes = wav(spec, 10);
+        let mut info = b"INFO".to_vec();
+        info.extend(chunk(*b"INAM", &[], b"Theme\0"));
+        info.extend(chunk(*b"IKEY", &[], b"loop\0"));
+        bytes.extend(chunk(*b"LIST", &[], &info));
+
+        let decoded = decode(&bytes).unwrap();
+        assert_eq!(decoded.bits_per_sample, Some(24));
+        assert_eq!(
+            decoded.tags,
+            [
+                ("TITLE".to_owned(), "Theme".to_owned()),
+                ("IKEY".to_owned(), "loop".to_owned()),
+            ]
+        );
     }
 }
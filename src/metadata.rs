@@ -0,0 +1,77 @@
+use {
+    bevy::{
+        reflect::{TypePath, TypeUuid},
+        utils::HashMap,
+    },
+    std::time::Duration,
+};
+
+/// Encoding of the file an audio asset was loaded from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AudioFormat {
+    /// FLAC.
+    Flac,
+    /// MP3.
+    Mp3,
+    /// Ogg Vorbis.
+    Ogg,
+    /// WAV.
+    Wav,
+}
+
+/// Details of the file an [`AudioSource`](crate::AudioSource) was loaded from.
+///
+/// Every file is also loaded as an `AudioMetadata` labeled `metadata`, such as
+/// `theme.ogg#metadata`. The sample rate and channels are those of the file,
+/// even if it was resampled or downmixed when it was loaded.
+///
+/// Tags are read from the Vorbis comments of Ogg Vorbis and FLAC files, the ID3 tags
+/// of MP3 files, and the `INFO` list of WAV files. Their keys are upper case, and
+/// common ID3 and `INFO` tags are renamed to their Vorbis comment names, such as
+/// `TITLE`, `ARTIST`, and `ALBUM`.
+#[derive(Debug, Clone, TypeUuid, TypePath)]
+#[uuid = "c4e2a7f1-5b3d-4c8e-9a6f-0d1b2e3f4a5c"]
+pub struct AudioMetadata {
+    /// Encoding of the file.
+    pub format: AudioFormat,
+    /// Sample rate of the file.
+    pub sample_rate: u32,
+    /// Number of channels in the file.
+    pub channels: usize,
+    /// Bits per sample of the file, for lossless formats.
+    pub bits_per_sample: Option<u32>,
+    /// How long the file plays for.
+    pub duration: Duration,
+    /// Every value of every tag, in the order they appear in the file.
+    pub tags: HashMap<String, Vec<String>>,
+}
+
+impl AudioMetadata {
+    /// The first value of the tag `key`, which is case-insensitive.
+    #[must_use]
+    pub fn tag(&self, key: &str) -> Option<&str> {
+        self.tags
+            .get(&key.to_uppercase())
+            .and_then(|values| values.first())
+            .map(String::as_str)
+    }
+
+    /// The title of the file.
+    #[must_use]
+    pub fn title(&self) -> Option<&str> {
+        self.tag("TITLE")
+    }
+
+    /// The artist of the file.
+    #[must_use]
+    pub fn artist(&self) -> Option<&str> {
+        self.tag("ARTIST")
+    }
+
+    /// The album of the file.
+    #[must_use]
+    pub fn album(&self) -> Option<&str> {
+        self.tag("ALBUM")
+    }
+}
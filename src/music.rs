@@ -0,0 +1,619 @@
+use {
+    crate::{
+        clock::{AudioClock, AudioInstant, SCHEDULE_AHEAD},
+        fade::{Fade, FadeCurve},
+        output::AudioSink,
+        Audio, AudioSource, AudioSourceSettings, PlayOptions, ToSignal,
+    },
+    bevy::{
+        prelude::{Assets, Event, EventWriter, Handle, Res, ResMut, Resource},
+        utils::tracing::warn,
+    },
+    oddio::Sample,
+};
+
+/// Music track played by a [`MusicPlayer`].
+pub type MusicSource = AudioSource<[Sample; 2]>;
+
+/// What a [`MusicPlayer`] does when a track finishes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RepeatMode {
+    /// Stop after the last track of the playlist.
+    #[default]
+    Off,
+    /// Repeat the current track forever.
+    One,
+    /// Start over from the first track after the last one.
+    All,
+}
+
+/// Sent when the track played by the [`MusicPlayer`] changes.
+///
+/// Tracks are identified by their index in [`MusicPlayer::playlist`].
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrackChanged {
+    /// The track that was playing, if any.
+    pub previous: Option<usize>,
+    /// The track that is now playing, or `None` if playback stopped.
+    pub current: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Action {
+    Play(Option<usize>),
+    Next,
+    Previous,
+    Stop,
+}
+
+struct Track {
+    index: usize,
+    sink: Handle<AudioSink<MusicSource>>,
+    start: AudioInstant,
+    end: AudioInstant,
+    /// Position at which to fade out into the next track, until it is sent to the sink.
+    fade_out_at: Option<f64>,
+}
+
+/// A sink that should fade out and stop.
+struct Stopping {
+    sink: Handle<AudioSink<MusicSource>>,
+    duration: f32,
+    curve: FadeCurve,
+    /// Whether the sink may still be waiting in the play queue.
+    queued: bool,
+}
+
+impl Stopping {
+    fn new(track: Track, duration: f32, curve: FadeCurve) -> Self {
+        Self {
+            sink: track.sink,
+            duration,
+            curve,
+            queued: true,
+        }
+    }
+}
+
+/// Plays a playlist of [`MusicSource`]s on the stereo [`AudioOutput`](crate::output::AudioOutput).
+///
+/// Tracks are scheduled on the output's clock, so consecutive tracks play
+/// without gaps, and overlap by the crossfade duration if one is set.
+///
+/// Playback is driven by the [`update_music_player`] system. Tracks are only
+/// started once their asset is loaded.
+#[derive(Resource)]
+pub struct MusicPlayer {
+    playlist: Vec<Handle<MusicSource>>,
+    /// Indices into `playlist`, in the order they are played.
+    order: Vec<usize>,
+    repeat: RepeatMode,
+    shuffle: bool,
+    crossfade: (f32, FadeCurve),
+    volume: f32,
+    paused_at: Option<AudioInstant>,
+    pause_requested: bool,
+    actions: Vec<Action>,
+    current: Option<Track>,
+    next: Option<Track>,
+    /// Tracks that the next one took over from, still playing until their end.
+    ending: Vec<Track>,
+    stopping: Vec<Stopping>,
+}
+
+impl Default for MusicPlayer {
+    fn default() -> Self {
+        Self {
+            playlist: Vec::new(),
+            order: Vec::new(),
+            repeat: RepeatMode::default(),
+            shuffle: false,
+            crossfade: (0.0, FadeCurve::EqualPower),
+            volume: 1.0,
+            paused_at: None,
+            pause_requested: false,
+            actions: Vec::new(),
+            current: None,
+            next: None,
+            ending: Vec::new(),
+            stopping: Vec::new(),
+        }
+    }
+}
+
+impl MusicPlayer {
+    /// Construct an empty `MusicPlayer` with no crossfade.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The tracks of the playlist, in the order they were added.
+    #[must_use]
+    pub fn playlist(&self) -> &[Handle<MusicSource>] {
+        &self.playlist
+    }
+
+    /// Replace the playlist. Stops the current track.
+    pub fn set_playlist(&mut self, playlist: Vec<Handle<MusicSource>>) {
+        self.stop();
+        self.playlist = playlist;
+        self.order = (0..self.playlist.len()).collect();
+        if self.shuffle {
+            fastrand::shuffle(&mut self.order);
+        }
+    }
+
+    /// Add a track to the end of the playlist.
+    pub fn push(&mut self, track: Handle<MusicSource>) {
+        self.order.push(self.playlist.len());
+        self.playlist.push(track);
+    }
+
+    /// Start playing the playlist from its first track, or resume it if paused.
+    pub fn play(&mut self) {
+        if self.is_paused() {
+            self.resume();
+        } else {
+            self.actions.push(Action::Play(None));
+        }
+    }
+
+    /// Play the track at `index` in the playlist, crossfading from the current one.
+    ///
+    /// Indices outside the playlist are ignored with a warning.
+    pub fn play_track(&mut self, index: usize) {
+        self.pause_requested = false;
+        self.actions.push(Action::Play(Some(index)));
+    }
+
+    /// Crossfade to the next track.
+    ///
+    /// Unlike the end of a track, this ignores [`RepeatMode::One`].
+    pub fn next(&mut self) {
+        self.actions.push(Action::Next);
+    }
+
+    /// Crossfade to the previous track.
+    pub fn previous(&mut self) {
+        self.actions.push(Action::Previous);
+    }
+
+    /// Fade out the current track over the crossfade duration and stop playback.
+    pub fn stop(&mut self) {
+        self.pause_requested = false;
+        self.actions.push(Action::Stop);
+    }
+
+    /// Pause playback.
+    pub fn pause(&mut self) {
+        self.pause_requested = true;
+    }
+
+    /// Resume paused playback.
+    pub fn resume(&mut self) {
+        self.pause_requested = false;
+    }
+
+    /// Whether playback is paused.
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.pause_requested
+    }
+
+    /// Index of the track currently playing, if any.
+    #[must_use]
+    pub fn current(&self) -> Option<usize> {
+        self.current.as_ref().map(|track| track.index)
+    }
+
+    /// What happens when a track finishes.
+    #[must_use]
+    pub fn repeat(&self) -> RepeatMode {
+        self.repeat
+    }
+
+    /// Set what happens when a track finishes.
+    pub fn set_repeat(&mut self, repeat: RepeatMode) {
+        self.repeat = repeat;
+    }
+
+    /// Whether the playlist is played in a random order.
+    #[must_use]
+    pub fn shuffle(&self) -> bool {
+        self.shuffle
+    }
+
+    /// Play the playlist in a random order.
+    ///
+    /// The order is shuffled again every time the playlist repeats.
+    pub fn set_shuffle(&mut self, shuffle: bool) {
+        self.shuffle = shuffle;
+        if shuffle {
+            fastrand::shuffle(&mut self.order);
+        } else {
+            self.order.sort_unstable();
+        }
+    }
+
+    /// Overlap consecutive tracks by `duration` seconds, fading between them with `curve`.
+    ///
+    /// A duration of `0.0` plays the tracks back to back without a gap.
+    pub fn set_crossfade(&mut self, duration: f32, curve: FadeCurve) {
+        self.crossfade = (duration.max(0.0), curve);
+    }
+
+    /// The volume of the music.
+    #[must_use]
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    /// Set the volume of the music.
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume;
+    }
+
+    fn slot(&self, index: usize) -> Option<usize> {
+        self.order.iter().position(|&i| i == index)
+    }
+
+    /// The track following `index` in the play order, reshuffling the order
+    /// if the playlist wraps around.
+    ///
+    /// `finished` is whether `index` finished on its own rather than being skipped.
+    fn following(&mut self, index: usize, finished: bool) -> Option<usize> {
+        if finished && self.repeat == RepeatMode::One {
+            return Some(index);
+        }
+        let slot = self.slot(index)?;
+        if let Some(&next) = self.order.get(slot + 1) {
+            return Some(next);
+        }
+        if self.repeat == RepeatMode::Off {
+            return None;
+        }
+        if self.shuffle && self.order.len() > 1 {
+            fastrand::shuffle(&mut self.order);
+            if self.order[0] == index {
+                let last = self.order.len() - 1;
+                self.order.swap(0, last);
+            }
+        }
+        self.order.first().copied()
+    }
+
+    fn preceding(&self, index: usize) -> Option<usize> {
+        match self.slot(index)? {
+            0 if self.repeat == RepeatMode::Off => Some(index),
+            0 => self.order.last().copied(),
+            slot => Some(self.order[slot - 1]),
+        }
+    }
+
+    fn fade_out(&mut self, track: Track) {
+        let (duration, curve) = self.crossfade;
+        self.stopping.push(Stopping::new(track, duration, curve));
+    }
+}
+
+/// System that drives the [`MusicPlayer`].
+#[allow(clippy::needless_pass_by_value, clippy::too_many_lines)]
+pub fn update_music_player(
+    mut player: ResMut<MusicPlayer>,
+    audio: Res<Audio<[Sample; 2], MusicSource>>,
+    sources: Res<Assets<MusicSource>>,
+    mut sinks: ResMut<Assets<AudioSink<MusicSource>>>,
+    clock: Res<AudioClock>,
+    mut track_changed: EventWriter<TrackChanged>,
+) {
+    let player = &mut *player;
+    let now = clock.now();
+    let (crossfade, curve) = player.crossfade;
+
+    let runtime = |playlist: &[Handle<MusicSource>], index: usize| -> Option<f64> {
+        let source = sources.get(playlist.get(index)?)?;
+        Some(source.frames.runtime())
+    };
+
+    // Pausing freezes every sink, including the scheduled next track,
+    // so the tracks stay aligned. Only the bookkeeping needs to be shifted.
+    match (player.pause_requested, player.paused_at) {
+        (true, None) => player.paused_at = Some(now),
+        (false, Some(paused_at)) => {
+            let paused_for = now - paused_at;
+            for track in player
+                .current
+                .iter_mut()
+                .chain(player.next.iter_mut())
+                .chain(player.ending.iter_mut())
+            {
+                track.start = track.start + paused_for;
+                track.end = track.end + paused_for;
+            }
+            player.paused_at = None;
+        }
+        _ => {}
+    }
+
+    let actions = std::mem::take(&mut player.actions);
+    for (i, action) in actions.iter().enumerate() {
+        let previous = player.current();
+        let target = match *action {
+            Action::Play(None) if previous.is_some() => continue,
+            Action::Play(None) => player.order.first().copied(),
+            Action::Play(Some(index)) if index >= player.playlist.len() => {
+                warn!(
+                    "Cannot play track {index} of a playlist with {} tracks.",
+                    player.playlist.len()
+                );
+                continue;
+            }
+            Action::Play(Some(index)) => Some(index),
+            Action::Next => previous.and_then(|index| player.following(index, false)),
+            Action::Previous => previous.and_then(|index| player.preceding(index)),
+            Action::Stop => None,
+        };
+
+        let length = target.map(|index| runtime(&player.playlist, index));
+        if length == Some(None) {
+            // Wait for the track to load.
+            player.actions.extend_from_slice(&actions[i..]);
+            break;
+        }
+
+        if let Some(track) = player.next.take() {
+            player.stopping.push(Stopping::new(track, 0.0, curve));
+        }
+        let fading_out = player.current.take();
+        let crossfading = fading_out.is_some();
+        if let Some(track) = fading_out {
+            player.fade_out(track);
+        }
+
+        if let (Some(index), Some(Some(length))) = (target, length) {
+            let start = now + SCHEDULE_AHEAD;
+            let mut options = PlayOptions::new().start_at(start);
+            if crossfading && crossfade > 0.0 {
+                options = options.fade_in(crossfade, curve);
+            }
+            let sink = audio.play_with_options(
+                player.playlist[index].clone_weak(),
+                AudioSourceSettings::default(),
+                options,
+            );
+            player.current = Some(Track {
+                index,
+                sink,
+                start,
+                end: start + length,
+                fade_out_at: None,
+            });
+        }
+
+        let current = player.current();
+        if current != previous {
+            track_changed.send(TrackChanged { previous, current });
+        }
+    }
+
+    if player.paused_at.is_none() {
+        if player.next.as_ref().is_some_and(|track| now >= track.start) {
+            let previous = player.current.take();
+            player.current = player.next.take();
+            track_changed.send(TrackChanged {
+                previous: previous.as_ref().map(|track| track.index),
+                current: player.current(),
+            });
+            player.ending.extend(previous);
+        } else if player.next.is_none()
+            && player
+                .current
+                .as_ref()
+                .is_some_and(|track| now >= track.end)
+        {
+            let previous = player.current.take();
+            track_changed.send(TrackChanged {
+                previous: previous.as_ref().map(|track| track.index),
+                current: None,
+            });
+            if let Some(track) = previous {
+                player.stopping.push(Stopping::new(track, 0.0, curve));
+            }
+        }
+
+        // Tracks that ended on their own are stopped too, so their sinks are cleaned up.
+        let (ended, ending) = std::mem::take(&mut player.ending)
+            .into_iter()
+            .partition(|track| now >= track.end);
+        player.ending = ending;
+        for track in ended {
+            player.stopping.push(Stopping::new(track, 0.0, curve));
+        }
+    }
+
+    // Schedule the following track as soon as the current one starts,
+    // so that it is in the mixer well before it has to be heard.
+    let schedule = match (&player.next, &player.current) {
+        (None, Some(current)) => runtime(&player.playlist, current.index)
+            .map(|current_length| (current.index, current.end, current_length)),
+        _ => None,
+    };
+    if let Some((index, end, current_length)) = schedule {
+        let following = player
+            .following(index, true)
+            .and_then(|following| Some((following, runtime(&player.playlist, following)?)));
+        if let Some((following, length)) = following {
+            let overlap = f64::from(crossfade).min(current_length).min(length);
+            let start = end - overlap;
+            let mut options = PlayOptions::new().start_at(start);
+            if overlap > 0.0 {
+                if let Some(current) = &mut player.current {
+                    current.fade_out_at = Some(current_length - overlap);
+                }
+                #[allow(clippy::cast_possible_truncation)]
+                {
+                    options = options.fade_in(overlap as f32, curve);
+                }
+            }
+            let sink = audio.play_with_options(
+                player.playlist[following].clone_weak(),
+                AudioSourceSettings::default(),
+                options,
+            );
+            player.next = Some(Track {
+                index: following,
+                sink,
+                start,
+                end: start + length,
+                fade_out_at: None,
+            });
+        }
+    }
+
+    // Sinks only exist once the queued audio has been played, after this system,
+    // so everything below is retried until they do. A sink that is still missing
+    // a frame later was removed, or its source failed to load.
+    player.stopping.retain_mut(|stopping| {
+        let Some(sink) = sinks.get_mut(&stopping.sink) else {
+            return std::mem::take(&mut stopping.queued);
+        };
+        if stopping.duration > 0.0 && !sink.is_paused() {
+            sink.stop_with_fade(stopping.duration, stopping.curve);
+        } else {
+            sink.stop();
+        }
+        false
+    });
+
+    let paused = player.paused_at.is_some();
+    let volume = player.volume;
+    for track in player
+        .current
+        .iter_mut()
+        .chain(player.next.iter_mut())
+        .chain(player.ending.iter_mut())
+    {
+        let Some(sink) = sinks.get_mut(&track.sink) else {
+            continue;
+        };
+        if let Some(position) = track.fade_out_at.take() {
+            #[allow(clippy::cast_possible_truncation)]
+            let duration = (track.end.as_secs() - track.start.as_secs() - position) as f32;
+            sink.control::<Fade<<MusicSource as ToSignal>::Signal>, _>()
+                .stop_with_fade_at(position, duration, curve);
+        }
+        if paused && !sink.is_paused() {
+            sink.pause();
+        } else if !paused && sink.is_paused() {
+            sink.resume();
+        }
+        sink.set_volume(volume);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{update_music_player, MusicPlayer, MusicSource, TrackChanged},
+        crate::{
+            clock::{update_audio_clock, AudioClock, Clock},
+            output::{
+                clean_stopped_audio_sinks, play_queued_audio, AudioOutput, AudioSink, AudioSinks,
+            },
+            Audio, AudioSource,
+        },
+        bevy::{
+            asset::{AddAsset, AssetPlugin, Assets},
+            core::TaskPoolPlugin,
+            ecs::event::Events,
+            prelude::{App, IntoSystemConfigs, Update},
+        },
+        oddio::{Frames, Mixer, Sample},
+        std::sync::Arc,
+    };
+
+    fn app(clock: &Arc<Clock>) -> App {
+        clock.set_sample_rate(4);
+        let (mixer, _signal) = oddio::split(Mixer::<[Sample; 2]>::new());
+        let mut app = App::new();
+        app.add_plugins((TaskPoolPlugin::default(), AssetPlugin::default()))
+            .add_asset::<MusicSource>()
+            .add_asset::<AudioSink<MusicSource>>()
+            .add_event::<TrackChanged>()
+            .insert_resource(AudioOutput::with_mixer(mixer))
+            .insert_resource(AudioClock::<AudioOutput<[Sample; 2]>>::new(clock.clone()))
+            .init_resource::<Audio<[Sample; 2], MusicSource>>()
+            .init_resource::<AudioSinks<MusicSource>>()
+            .init_resource::<MusicPlayer>()
+            .add_systems(
+                Update,
+                (
+                    update_audio_clock::<AudioOutput<[Sample; 2]>>,
+                    update_music_player,
+                    play_queued_audio::<[Sample; 2], MusicSource>,
+                    clean_stopped_audio_sinks::<MusicSource>,
+                )
+                    .chain(),
+            );
+        app
+    }
+
+    #[test]
+    fn stops_tracks_that_end() {
+        let clock = Arc::new(Clock::default());
+        let mut app = app(&clock);
+        let mut sources = app.world.resource_mut::<Assets<MusicSource>>();
+        let playlist = (0..2)
+            .map(|_| sources.add(AudioSource::new(Frames::from_slice(4, &[[0.0; 2]; 2]))))
+            .collect();
+        let mut player = app.world.resource_mut::<MusicPlayer>();
+        player.set_playlist(playlist);
+        player.play();
+
+        let mut changes = Vec::new();
+        // The tracks last half a second each, and start a tenth of a second in.
+        for frames in [0, 3, 2] {
+            clock.advance(frames);
+            app.update();
+            changes.extend(app.world.resource_mut::<Events<TrackChanged>>().drain());
+            let sinks = app.world.resource::<Assets<AudioSink<MusicSource>>>();
+            assert_eq!(sinks.len(), [2, 1, 0][changes.len() - 1]);
+        }
+
+        let change = |previous, current| TrackChanged { previous, current };
+        assert_eq!(
+            changes,
+            [
+                change(None, Some(0)),
+                change(Some(0), Some(1)),
+                change(Some(1), None)
+            ]
+        );
+        assert!(app.world.resource::<AudioSinks<MusicSource>>().is_empty());
+        assert!(app.world.resource::<MusicPlayer>().stopping.is_empty());
+    }
+
+    #[test]
+    fn ignores_tracks_outside_playlist() {
+        let clock = Arc::new(Clock::default());
+        let mut app = app(&clock);
+        let source = app
+            .world
+            .resource_mut::<Assets<MusicSource>>()
+            .add(AudioSource::new(Frames::from_slice(4, &[[0.0; 2]; 8])));
+        let mut player = app.world.resource_mut::<MusicPlayer>();
+        player.set_playlist(vec![source]);
+        player.play();
+        app.update();
+        app.world.resource_mut::<Events<TrackChanged>>().clear();
+
+        app.world.resource_mut::<MusicPlayer>().play_track(1);
+        app.update();
+        assert_eq!(app.world.resource::<MusicPlayer>().current(), Some(0));
+        assert!(app.world.resource::<Events<TrackChanged>>().is_empty());
+        let mut sinks = app.world.resource_mut::<Assets<AudioSink<MusicSource>>>();
+        let (_, sink) = sinks.iter_mut().next().unwrap();
+        assert!(!sink.is_stopped());
+    }
+}
@@ -1,7 +1,9 @@
 use {
     crate::{
+        clock::{AudioClock, AudioInstant, Clock, Scheduled},
+        fade::Fade,
         frames::{frame_n, ArrayLength, AsArray},
-        Audio, StreamConfig, ToSignal,
+        Audio, PlayOptions, SignalContext, StreamConfig, ToSignal,
     },
     bevy::{
         asset::{Asset, Handle as BevyHandle, HandleId},
@@ -15,9 +17,145 @@ use {
         Device, SupportedBufferSize, SupportedStreamConfigRange,
     },
     oddio::{Frame, Handle as OddioHandle, Mixer, Signal, SplitSignal, Stop},
-    std::mem::ManuallyDrop,
+    std::{mem::ManuallyDrop, sync::Arc},
 };
 
+/// Generates typed controls for a sink that wraps an [`oddio::Handle`].
+///
+/// `$chain` is the filter chain held by the handle, and must contain
+/// a [`Stop`] wrapping a [`SinkSignal`].
+macro_rules! impl_sink_controls {
+    ($sink:ident, $chain:ty) => {
+        impl<Source> $sink<Source>
+        where
+            Source: $crate::ToSignal + ::bevy::asset::Asset,
+            Source::Signal: 'static,
+        {
+            /// Suspend playback of the sink.
+            pub fn pause(&mut self) {
+                self.control::<::oddio::Stop<$crate::output::SinkSignal<Source>>, _>()
+                    .pause();
+            }
+
+            /// Resume playback of a paused sink.
+            ///
+            /// This also resumes sinks paused by [`pause_with_fade`](Self::pause_with_fade),
+            /// without fading them back in.
+            pub fn resume(&mut self) {
+                self.control::<::oddio::Stop<$crate::output::SinkSignal<Source>>, _>()
+                    .resume();
+                let mut fade = self.control::<$crate::fade::Fade<Source::Signal>, _>();
+                if fade.is_paused() {
+                    fade.resume();
+                }
+            }
+
+            /// Stop the sink for good.
+            pub fn stop(&mut self) {
+                self.control::<::oddio::Stop<$crate::output::SinkSignal<Source>>, _>()
+                    .stop();
+            }
+
+            /// Whether the sink is paused.
+            pub fn is_paused(&mut self) -> bool {
+                self.control::<::oddio::Stop<$crate::output::SinkSignal<Source>>, _>()
+                    .is_paused()
+                    || self
+                        .control::<$crate::fade::Fade<Source::Signal>, _>()
+                        .is_paused()
+            }
+
+            /// Whether the sink has been stopped.
+            pub fn is_stopped(&mut self) -> bool {
+                self.control::<::oddio::Stop<$crate::output::SinkSignal<Source>>, _>()
+                    .is_stopped()
+            }
+
+            /// Fade the sink to full volume over `duration` seconds.
+            ///
+            /// Resumes the sink if it was paused with [`pause_with_fade`](Self::pause_with_fade).
+            pub fn resume_with_fade(&mut self, duration: f32, curve: $crate::fade::FadeCurve) {
+                self.control::<::oddio::Stop<$crate::output::SinkSignal<Source>>, _>()
+                    .resume();
+                self.control::<$crate::fade::Fade<Source::Signal>, _>()
+                    .fade_in(duration, curve);
+            }
+
+            /// Fade the sink to silence over `duration` seconds, then pause it.
+            pub fn pause_with_fade(&mut self, duration: f32, curve: $crate::fade::FadeCurve) {
+                self.control::<$crate::fade::Fade<Source::Signal>, _>()
+                    .pause_with_fade(duration, curve);
+            }
+
+            /// Fade the sink to silence over `duration` seconds, then stop it for good.
+            ///
+            /// The sink is removed from its storage once the fade completes.
+            pub fn stop_with_fade(&mut self, duration: f32, curve: $crate::fade::FadeCurve) {
+                self.control::<$crate::fade::Fade<Source::Signal>, _>()
+                    .stop_with_fade(duration, curve);
+            }
+
+            /// Get the current amplitude scaling factor.
+            ///
+            /// Only available if the signal contains a [`Gain`](oddio::Gain).
+            pub fn volume<T, Index>(&mut self) -> f32
+            where
+                $chain: ::oddio::FilterHaving<::oddio::Gain<T>, Index>,
+                T: 'static,
+            {
+                self.control::<::oddio::Gain<T>, Index>().amplitude_ratio()
+            }
+
+            /// Scale the amplitude of the signal.
+            ///
+            /// Only available if the signal contains a [`Gain`](oddio::Gain).
+            /// See [`GainControl::set_amplitude_ratio`](oddio::GainControl::set_amplitude_ratio).
+            pub fn set_volume<T, Index>(&mut self, volume: f32)
+            where
+                $chain: ::oddio::FilterHaving<::oddio::Gain<T>, Index>,
+                T: 'static,
+            {
+                self.control::<::oddio::Gain<T>, Index>()
+                    .set_amplitude_ratio(volume);
+            }
+
+            /// Amplify the signal by `db` decibels.
+            ///
+            /// Only available if the signal contains a [`Gain`](oddio::Gain).
+            /// See [`GainControl::set_gain`](oddio::GainControl::set_gain).
+            pub fn set_gain<T, Index>(&mut self, db: f32)
+            where
+                $chain: ::oddio::FilterHaving<::oddio::Gain<T>, Index>,
+                T: 'static,
+            {
+                self.control::<::oddio::Gain<T>, Index>().set_gain(db);
+            }
+
+            /// Get the current playback speed.
+            ///
+            /// Only available if the signal contains a [`Speed`](oddio::Speed).
+            pub fn speed<T, Index>(&mut self) -> f32
+            where
+                $chain: ::oddio::FilterHaving<::oddio::Speed<T>, Index>,
+                T: 'static,
+            {
+                self.control::<::oddio::Speed<T>, Index>().speed()
+            }
+
+            /// Adjust the playback speed.
+            ///
+            /// Only available if the signal contains a [`Speed`](oddio::Speed).
+            pub fn set_speed<T, Index>(&mut self, speed: f32)
+            where
+                $chain: ::oddio::FilterHaving<::oddio::Speed<T>, Index>,
+                T: 'static,
+            {
+                self.control::<::oddio::Speed<T>, Index>().set_speed(speed);
+            }
+        }
+    };
+}
+
 /// Spatial audio output.
 pub mod spatial;
 
@@ -25,15 +163,45 @@ pub mod spatial;
 #[derive(Resource)]
 pub struct AudioOutput<F> {
     mixer_handle: OddioHandle<Mixer<F>>,
+    clock: Arc<Clock>,
 }
 
 impl<F: Frame + 'static> AudioOutput<F> {
-    fn play<S>(&mut self, signal: S::Signal) -> AudioSink<S>
+    /// Construct an output that plays into `mixer_handle` instead of an audio device.
+    ///
+    /// The mixer is not run by this crate, so render it with [`oddio::run`].
+    /// The output's clock stays at zero.
+    #[must_use]
+    pub fn with_mixer(mixer_handle: OddioHandle<Mixer<F>>) -> Self {
+        Self {
+            mixer_handle,
+            clock: Arc::default(),
+        }
+    }
+
+    fn play<S>(&mut self, signal: S::Signal, options: PlayOptions) -> AudioSink<S>
     where
         S: ToSignal + Asset,
         S::Signal: Signal<Frame = F> + Send,
     {
-        AudioSink(ManuallyDrop::new(self.mixer_handle.control().play(signal)))
+        AudioSink(ManuallyDrop::new(
+            self.mixer_handle
+                .control()
+                .play(options.wrap(signal, self.clock.clone())),
+        ))
+    }
+
+    /// The current time on this output's clock.
+    ///
+    /// See [`Audio::play_at`].
+    #[must_use]
+    pub fn now(&self) -> AudioInstant {
+        self.clock.now()
+    }
+
+    #[cfg(any(feature = "flac", feature = "mp3", feature = "ogg", feature = "wav"))]
+    pub(crate) fn clock(&self) -> &Arc<Clock> {
+        &self.clock
     }
 }
 
@@ -48,16 +216,32 @@ impl<F: Frame + AsArray + Clone + 'static> FromWorld for AudioOutput<F> {
             supported_config_range = stream_config.0.clone();
         }
 
+        // Set before the stream starts, so that loaders can resample to it right away.
+        let clock = Arc::new(Clock::default());
+        clock.set_sample_rate(supported_config_range.max_sample_rate().0);
+        let stream_clock = clock.clone();
+
         task_pool
-            .spawn(async move { play(mixer, &device, &supported_config_range) })
+            .spawn(async move { play(mixer, &stream_clock, &device, &supported_config_range) })
             .detach();
 
-        Self { mixer_handle }
+        Self {
+            mixer_handle,
+            clock,
+        }
+    }
+}
+
+impl<F: Frame + AsArray + Clone + 'static> FromWorld for AudioClock<AudioOutput<F>> {
+    fn from_world(world: &mut bevy::prelude::World) -> Self {
+        world.init_resource::<AudioOutput<F>>();
+        AudioClock::new(world.resource::<AudioOutput<F>>().clock.clone())
     }
 }
 
 fn play<F>(
     mixer: SplitSignal<Mixer<F>>,
+    clock: &Arc<Clock>,
     device: &Device,
     supported_config_range: &SupportedStreamConfigRange,
 ) where
@@ -72,10 +256,14 @@ fn play<F>(
         sample_rate: supported_config_range.max_sample_rate(),
         buffer_size,
     };
+    let clock = clock.clone();
+    let mut origin = None;
     let stream = device
         .build_output_stream(
             &config,
-            move |out_flat: &mut [f32], _: &cpal::OutputCallbackInfo| {
+            move |out_flat: &mut [f32], info: &cpal::OutputCallbackInfo| {
+                let timestamp = info.timestamp();
+                clock.record_timestamp(*origin.get_or_insert(timestamp.callback), timestamp);
                 assert_eq!(
                     out_flat.len() % F::Array::LENGTH,
                     0,
@@ -86,6 +274,7 @@ fn play<F>(
                 // (2) out_flat.len() is divisible by `N`.
                 let out_n = unsafe { frame_n(out_flat) };
                 oddio::run(&mixer, config.sample_rate.0, out_n);
+                clock.advance(out_n.len());
             },
             move |err| bevy::utils::tracing::error!("Error in cpal: {err:?}"),
             None
@@ -97,11 +286,12 @@ fn play<F>(
 }
 
 /// System to play queued audio in [`Audio`].
-#[allow(clippy::needless_pass_by_value, clippy::missing_panics_doc)]
+#[allow(clippy::needless_pass_by_value)]
 pub fn play_queued_audio<F, Source>(
     mut audio_output: ResMut<AudioOutput<F>>,
     audio: Res<Audio<F, Source>>,
     sources: Res<Assets<Source>>,
+    context: SignalContext,
     mut sink_assets: ResMut<Assets<AudioSink<Source>>>,
     mut sinks: ResMut<AudioSinks<Source>>,
 ) where
@@ -109,32 +299,39 @@ pub fn play_queued_audio<F, Source>(
     Source::Signal: Signal<Frame = F> + Send,
     F: Frame + 'static,
 {
-    let mut queue = audio.queue.write();
-    let len = queue.len();
-    let mut i = 0;
-    while i < len {
-        let config = queue.pop_front().unwrap(); // This should not panic
-        if let Some(audio_source) = sources.get(&config.source_handle) {
-            if config.spatial_settings.is_some() {
-                return;
+    for mut config in audio.queue.drain() {
+        if config.spatial_settings.is_some() {
+            continue;
+        }
+        let signal = match config.source.into_signal(&sources, &context) {
+            Ok(signal) => signal,
+            Err(source) => {
+                config.source = source;
+                audio.queue.push(config);
+                continue;
             }
-            let sink = audio_output.play::<Source>(audio_source.to_signal(config.settings));
+        };
+        let sink = audio_output.play::<Source>(signal, config.options);
+        if let Some(stop_handle) = config.stop_handle {
             // Unlike bevy_audio, we should not drop this
-            let sink_handle = sink_assets.set(config.stop_handle, sink);
+            let sink_handle = sink_assets.set(stop_handle, sink);
             sinks.insert(sink_handle.id(), sink_handle.clone());
         } else {
-            queue.push_back(config);
+            // One-shot sounds keep playing until they finish on their own.
+            drop(ManuallyDrop::into_inner(sink.0));
         }
-        i += 1;
     }
 }
 
+/// The signal of a [`ToSignal`] source, as wrapped by every sink.
+pub type SinkSignal<Source> = Scheduled<Fade<<Source as ToSignal>::Signal>>;
+
 /// Asset that controls the playback of the sound.
 #[derive(TypeUuid, TypePath, Deref, DerefMut)]
 #[uuid = "82317ee9-8f2d-4973-bb7f-8f4a5b74cc55"]
-pub struct AudioSink<Source: ToSignal + Asset>(
-    ManuallyDrop<OddioHandle<Stop<<Source as ToSignal>::Signal>>>,
-);
+pub struct AudioSink<Source: ToSignal + Asset>(ManuallyDrop<OddioHandle<Stop<SinkSignal<Source>>>>);
+
+impl_sink_controls!(AudioSink, Stop<SinkSignal<Source>>);
 
 /// Storage of all audio sinks.
 #[derive(Resource, Deref, DerefMut)]
@@ -146,6 +343,30 @@ impl<Source: ToSignal + Asset> Default for AudioSinks<Source> {
     }
 }
 
+/// System to remove stopped sinks from [`AudioSinks`] and [`Assets`].
+#[allow(clippy::needless_pass_by_value)]
+pub fn clean_stopped_audio_sinks<Source>(
+    mut sink_assets: ResMut<Assets<AudioSink<Source>>>,
+    mut sinks: ResMut<AudioSinks<Source>>,
+) where
+    Source: ToSignal + Asset + Send,
+    Source::Signal: Signal + Send,
+{
+    sinks.retain(|_, handle| {
+        let stopped = match sink_assets.get_mut(handle) {
+            Some(sink) => sink.is_stopped(),
+            None => true,
+        };
+        if stopped {
+            if let Some(mut sink) = sink_assets.remove(handle.id()) {
+                // SAFETY: the sink has been removed from `Assets`, so the handle is never used again.
+                unsafe { ManuallyDrop::drop(&mut sink.0) };
+            }
+        }
+        !stopped
+    });
+}
+
 fn get_host_info() -> (Device, SupportedStreamConfigRange) {
     let host = cpal::default_host();
     let device = host
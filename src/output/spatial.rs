@@ -1,9 +1,13 @@
 use {
-    super::get_host_info,
-    crate::{Audio, AudioToPlay, BufferedSettings, SpatialSettings, ToSignal},
+    super::{get_host_info, SinkSignal},
+    crate::{
+        clock::{AudioClock, AudioInstant, Clock},
+        Audio, AudioToPlay, BufferedSettings, PlayOptions, PlaySource, SignalContext,
+        SpatialSettings, ToSignal,
+    },
     bevy::{
         asset::{Asset, Handle as BevyHandle, HandleId},
-        prelude::{Assets, Deref, DerefMut, Quat, Res, ResMut, Resource},
+        prelude::{Assets, Deref, DerefMut, FromWorld, Quat, Res, ResMut, Resource},
         reflect::{TypePath, TypeUuid},
         tasks::AsyncComputeTaskPool,
         utils::HashMap,
@@ -16,13 +20,14 @@ use {
         Frame, Handle as OddioHandle, Sample, Seek, Signal, Spatial, SpatialBuffered,
         SpatialOptions, SpatialScene, SplitSignal, Stop,
     },
-    std::mem::ManuallyDrop,
+    std::{mem::ManuallyDrop, sync::Arc},
 };
 
 /// Used internally in handling spatial audio output.
 #[derive(Resource)]
 pub struct SpatialAudioOutput {
     spatial_scene_handle: OddioHandle<SpatialScene>,
+    clock: Arc<Clock>,
 }
 
 impl SpatialAudioOutput {
@@ -37,19 +42,35 @@ impl SpatialAudioOutput {
             .set_listener_rotation(rotation.into());
     }
 
-    fn play<S>(&mut self, signal: S::Signal, options: SpatialOptions) -> SpatialAudioSink<S>
+    /// The current time on this output's clock.
+    ///
+    /// See [`PlayOptions::start_at`].
+    #[must_use]
+    pub fn now(&self) -> AudioInstant {
+        self.clock.now()
+    }
+
+    fn play<S>(
+        &mut self,
+        signal: S::Signal,
+        play_options: PlayOptions,
+        options: SpatialOptions,
+    ) -> SpatialAudioSink<S>
     where
         S: ToSignal + Asset,
         S::Signal: Seek + Signal<Frame = Sample> + Send,
     {
         SpatialAudioSink(ManuallyDrop::new(
-            self.spatial_scene_handle.control().play(signal, options),
+            self.spatial_scene_handle
+                .control()
+                .play(play_options.wrap(signal, self.clock.clone()), options),
         ))
     }
 
     fn play_buffered<S>(
         &mut self,
         signal: S::Signal,
+        play_options: PlayOptions,
         options: SpatialOptions,
         max_distance: f32,
         rate: u32,
@@ -61,7 +82,7 @@ impl SpatialAudioOutput {
     {
         SpatialBufferedAudioSink(ManuallyDrop::new(
             self.spatial_scene_handle.control().play_buffered(
-                signal,
+                play_options.wrap(signal, self.clock.clone()),
                 options,
                 max_distance,
                 rate,
@@ -78,18 +99,39 @@ impl Default for SpatialAudioOutput {
 
         let (device, supported_config_range) = get_host_info();
 
+        // Set before the stream starts, so that loaders can resample to it right away.
+        let clock = Arc::new(Clock::default());
+        clock.set_sample_rate(supported_config_range.max_sample_rate().0);
+        let stream_clock = clock.clone();
+
         task_pool
-            .spawn(async move { play(spatial_scene, &device, &supported_config_range) })
+            .spawn(async move {
+                play(
+                    spatial_scene,
+                    &stream_clock,
+                    &device,
+                    &supported_config_range,
+                );
+            })
             .detach();
 
         Self {
             spatial_scene_handle,
+            clock,
         }
     }
 }
 
+impl FromWorld for AudioClock<SpatialAudioOutput> {
+    fn from_world(world: &mut bevy::prelude::World) -> Self {
+        world.init_resource::<SpatialAudioOutput>();
+        AudioClock::new(world.resource::<SpatialAudioOutput>().clock.clone())
+    }
+}
+
 fn play(
     spatial_scene: SplitSignal<SpatialScene>,
+    clock: &Arc<Clock>,
     device: &Device,
     supported_config_range: &SupportedStreamConfigRange,
 ) {
@@ -102,12 +144,17 @@ fn play(
         sample_rate: supported_config_range.max_sample_rate(),
         buffer_size,
     };
+    let clock = clock.clone();
+    let mut origin = None;
     let stream = device
         .build_output_stream(
             &config,
-            move |out: &mut [f32], _: &cpal::OutputCallbackInfo| {
+            move |out: &mut [f32], info: &cpal::OutputCallbackInfo| {
+                let timestamp = info.timestamp();
+                clock.record_timestamp(*origin.get_or_insert(timestamp.callback), timestamp);
                 let out_stereo = oddio::frame_stereo(out);
                 oddio::run(&spatial_scene, config.sample_rate.0, out_stereo);
+                clock.advance(out_stereo.len());
             },
             move |err| bevy::utils::tracing::error!("Error in cpal: {err:?}"),
             None,
@@ -120,90 +167,156 @@ fn play(
 }
 
 /// System to play queued spatial audio in [`Audio`].
-#[allow(clippy::needless_pass_by_value, clippy::missing_panics_doc)]
+#[allow(clippy::needless_pass_by_value)]
 pub fn play_queued_spatial_audio<Source>(
     mut audio_output: ResMut<SpatialAudioOutput>,
     audio: Res<Audio<Sample, Source>>,
     sources: Res<Assets<Source>>,
+    context: SignalContext,
     mut sink_assets: ResMut<Assets<SpatialAudioSink<Source>>>,
     mut sinks: ResMut<SpatialAudioSinks<Source>>,
 ) where
     Source: ToSignal + Asset + Send,
     Source::Signal: Seek + Signal<Frame = Sample> + Send,
 {
-    let mut queue = audio.queue.write();
-    let len = queue.len();
-    let mut i = 0;
-    while i < len {
-        let config = queue.pop_front().unwrap(); // This should not panic
-        if let Some(audio_source) = sources.get(&config.source_handle) {
-            if let Some(spatial_settings) = config.spatial_settings {
-                let sink = audio_output.play::<Source>(
-                    audio_source.to_signal(config.settings),
-                    spatial_settings.options,
-                );
-                // Unlike bevy_audio, we should not drop this
-                let sink_handle = sink_assets.set(config.stop_handle, sink);
-                sinks.insert(sink_handle.id(), sink_handle.clone());
+    for mut config in audio.queue.drain() {
+        let Some(spatial_options) = config
+            .spatial_settings
+            .as_ref()
+            .map(|settings| settings.options)
+        else {
+            continue;
+        };
+        let signal = match config.source.into_signal(&sources, &context) {
+            Ok(signal) => signal,
+            Err(source) => {
+                config.source = source;
+                audio.queue.push(config);
+                continue;
             }
+        };
+        let sink = audio_output.play::<Source>(signal, config.options, spatial_options);
+        if let Some(stop_handle) = config.stop_handle {
+            // Unlike bevy_audio, we should not drop this
+            let sink_handle = sink_assets.set(stop_handle, sink);
+            sinks.insert(sink_handle.id(), sink_handle.clone());
         } else {
-            queue.push_back(config);
+            // One-shot sounds keep playing until they finish on their own.
+            drop(ManuallyDrop::into_inner(sink.0));
         }
-        i += 1;
     }
 }
 
 /// System to play queued spatial buffered audio in [`Audio`].
-#[allow(clippy::needless_pass_by_value, clippy::missing_panics_doc)]
+#[allow(clippy::needless_pass_by_value)]
 pub fn play_queued_spatial_buffered_audio<Source>(
     mut audio_output: ResMut<SpatialAudioOutput>,
     audio: Res<Audio<Sample, Source>>,
     sources: Res<Assets<Source>>,
+    context: SignalContext,
     mut sink_assets: ResMut<Assets<SpatialBufferedAudioSink<Source>>>,
     mut sinks: ResMut<SpatialBufferedAudioSinks<Source>>,
 ) where
     Source: ToSignal + Asset + Send,
     Source::Signal: Signal<Frame = Sample> + Send,
 {
-    let mut queue = audio.queue.write();
-    let len = queue.len();
-    let mut i = 0;
-    while i < len {
-        let config = queue.pop_front().unwrap(); // This should not panic
-        if let Some(audio_source) = sources.get(&config.source_handle) {
-            if let Some(spatial_settings) = config.spatial_settings {
-                if let Some(BufferedSettings {
+    for mut config in audio.queue.drain() {
+        let Some(SpatialSettings {
+            options: spatial_options,
+            buffered_settings:
+                Some(BufferedSettings {
                     max_distance,
                     rate,
                     buffer_duration,
-                }) = spatial_settings.buffered_settings
-                {
-                    let sink = audio_output.play_buffered::<Source>(
-                        audio_source.to_signal(config.settings),
-                        spatial_settings.options,
-                        max_distance,
-                        rate,
-                        buffer_duration,
-                    );
-                    // Unlike bevy_audio, we should not drop this
-                    let sink_handle = sink_assets.set(config.stop_handle, sink);
-                    sinks.insert(sink_handle.id(), sink_handle.clone());
-                }
+                }),
+        }) = config.spatial_settings
+        else {
+            continue;
+        };
+        let signal = match config.source.into_signal(&sources, &context) {
+            Ok(signal) => signal,
+            Err(source) => {
+                config.source = source;
+                audio.queue.push(config);
+                continue;
             }
+        };
+        let sink = audio_output.play_buffered::<Source>(
+            signal,
+            config.options,
+            spatial_options,
+            max_distance,
+            rate,
+            buffer_duration,
+        );
+        if let Some(stop_handle) = config.stop_handle {
+            // Unlike bevy_audio, we should not drop this
+            let sink_handle = sink_assets.set(stop_handle, sink);
+            sinks.insert(sink_handle.id(), sink_handle.clone());
         } else {
-            queue.push_back(config);
+            // One-shot sounds keep playing until they finish on their own.
+            drop(ManuallyDrop::into_inner(sink.0));
         }
-        i += 1;
     }
 }
 
+/// System to remove stopped sinks from [`SpatialAudioSinks`] and [`Assets`].
+#[allow(clippy::needless_pass_by_value)]
+pub fn clean_stopped_spatial_audio_sinks<Source>(
+    mut sink_assets: ResMut<Assets<SpatialAudioSink<Source>>>,
+    mut sinks: ResMut<SpatialAudioSinks<Source>>,
+) where
+    Source: ToSignal + Asset + Send,
+    Source::Signal: Signal + Send,
+{
+    sinks.retain(|_, handle| {
+        let stopped = match sink_assets.get_mut(handle) {
+            Some(sink) => sink.is_stopped(),
+            None => true,
+        };
+        if stopped {
+            if let Some(mut sink) = sink_assets.remove(handle.id()) {
+                // SAFETY: the sink has been removed from `Assets`, so the handle is never used again.
+                unsafe { ManuallyDrop::drop(&mut sink.0) };
+            }
+        }
+        !stopped
+    });
+}
+
+/// System to remove stopped sinks from [`SpatialBufferedAudioSinks`] and [`Assets`].
+#[allow(clippy::needless_pass_by_value)]
+pub fn clean_stopped_spatial_buffered_audio_sinks<Source>(
+    mut sink_assets: ResMut<Assets<SpatialBufferedAudioSink<Source>>>,
+    mut sinks: ResMut<SpatialBufferedAudioSinks<Source>>,
+) where
+    Source: ToSignal + Asset + Send,
+    Source::Signal: Signal + Send,
+{
+    sinks.retain(|_, handle| {
+        let stopped = match sink_assets.get_mut(handle) {
+            Some(sink) => sink.is_stopped(),
+            None => true,
+        };
+        if stopped {
+            if let Some(mut sink) = sink_assets.remove(handle.id()) {
+                // SAFETY: the sink has been removed from `Assets`, so the handle is never used again.
+                unsafe { ManuallyDrop::drop(&mut sink.0) };
+            }
+        }
+        !stopped
+    });
+}
+
 /// Asset that controls the playback of the spatial sound.
 #[derive(TypeUuid, TypePath, Deref, DerefMut)]
 #[uuid = "4b135d1c-68cb-4104-b5c5-4be8bea6c46c"]
 pub struct SpatialAudioSink<Source: ToSignal + Asset>(
-    ManuallyDrop<OddioHandle<Spatial<Stop<<Source as ToSignal>::Signal>>>>,
+    ManuallyDrop<OddioHandle<Spatial<Stop<SinkSignal<Source>>>>>,
 );
 
+impl_sink_controls!(SpatialAudioSink, Spatial<Stop<SinkSignal<Source>>>);
+
 /// Storage of all spatial audio sinks.
 #[derive(Resource, Deref, DerefMut)]
 pub struct SpatialAudioSinks<Source: ToSignal + Asset>(
@@ -220,7 +333,12 @@ impl<Source: ToSignal + Asset> Default for SpatialAudioSinks<Source> {
 #[derive(TypeUuid, TypePath, Deref, DerefMut)]
 #[uuid = "4b135d1c-68cb-4104-b5c5-4be8bea6c46c"]
 pub struct SpatialBufferedAudioSink<Source: ToSignal + Asset>(
-    ManuallyDrop<OddioHandle<SpatialBuffered<Stop<<Source as ToSignal>::Signal>>>>,
+    ManuallyDrop<OddioHandle<SpatialBuffered<Stop<SinkSignal<Source>>>>>,
+);
+
+impl_sink_controls!(
+    SpatialBufferedAudioSink,
+    SpatialBuffered<Stop<SinkSignal<Source>>>
 );
 
 /// Storage of all spatial audio sinks.
@@ -248,22 +366,99 @@ where
     ///
     /// Returns a handle that can be paused or permanently stopped.
     pub fn play_spatial(
-        &mut self,
+        &self,
         source_handle: BevyHandle<Source>,
         settings: Source::Settings,
         spatial_options: SpatialOptions,
     ) -> BevyHandle<SpatialAudioSink<Source>> {
-        let stop_handle = HandleId::random::<SpatialAudioSink<Source>>();
-        let audio_to_play = AudioToPlay {
+        self.play_spatial_with_options(
             source_handle,
-            stop_handle,
             settings,
+            PlayOptions::default(),
+            spatial_options,
+        )
+    }
+
+    /// Play the given type that implements [`Signal`] with the given [`PlayOptions`].
+    ///
+    /// See [`Audio::play_spatial`].
+    pub fn play_spatial_with_options(
+        &self,
+        source_handle: BevyHandle<Source>,
+        settings: Source::Settings,
+        options: PlayOptions,
+        spatial_options: SpatialOptions,
+    ) -> BevyHandle<SpatialAudioSink<Source>> {
+        let stop_handle = HandleId::random::<SpatialAudioSink<Source>>();
+        let audio_to_play = AudioToPlay {
+            source: PlaySource::Asset(source_handle, settings),
+            stop_handle: Some(stop_handle),
+            options,
+            spatial_settings: Some(SpatialSettings {
+                options: spatial_options,
+                buffered_settings: None,
+            }),
+        };
+        self.queue.push(audio_to_play);
+        BevyHandle::<SpatialAudioSink<Source>>::weak(stop_handle)
+    }
+
+    /// Play the given type that implements [`Signal`], without a sink to control it.
+    ///
+    /// See [`Audio::play_spatial`] and [`Audio::play_oneshot`].
+    pub fn play_spatial_oneshot(
+        &self,
+        source_handle: BevyHandle<Source>,
+        settings: Source::Settings,
+        spatial_options: SpatialOptions,
+    ) {
+        let audio_to_play = AudioToPlay {
+            source: PlaySource::Asset(source_handle, settings),
+            stop_handle: None,
+            options: PlayOptions::default(),
+            spatial_settings: Some(SpatialSettings {
+                options: spatial_options,
+                buffered_settings: None,
+            }),
+        };
+        self.queue.push(audio_to_play);
+    }
+
+    /// Play a signal that has already been created in the spatial scene.
+    ///
+    /// Use [`DynamicSpatialSignal`](crate::dynamic::DynamicSpatialSignal) as the source
+    /// to play any boxed signal without registering a source type.
+    ///
+    /// See [`Audio::play_signal`] and [`Audio::play_spatial`].
+    pub fn play_spatial_signal(
+        &self,
+        signal: Source::Signal,
+        spatial_options: SpatialOptions,
+    ) -> BevyHandle<SpatialAudioSink<Source>> {
+        self.play_spatial_signal_with_options(signal, PlayOptions::default(), spatial_options)
+    }
+
+    /// Play a signal that has already been created in the spatial scene
+    /// with the given [`PlayOptions`].
+    ///
+    /// See [`Audio::play_spatial_signal`].
+    pub fn play_spatial_signal_with_options(
+        &self,
+        signal: Source::Signal,
+        options: PlayOptions,
+        spatial_options: SpatialOptions,
+    ) -> BevyHandle<SpatialAudioSink<Source>> {
+        let stop_handle = HandleId::random::<SpatialAudioSink<Source>>();
+        let audio_to_play = AudioToPlay {
+            source: PlaySource::Signal(signal),
+            stop_handle: Some(stop_handle),
+            options,
             spatial_settings: Some(SpatialSettings {
                 options: spatial_options,
                 buffered_settings: None,
             }),
         };
-        self.queue.write().push_back(audio_to_play);
+        self.queue.push(audio_to_play);
         BevyHandle::<SpatialAudioSink<Source>>::weak(stop_handle)
     }
 }
@@ -280,7 +475,7 @@ where
     ///
     /// Returns a handle that can be paused or permanently stopped.
     pub fn play_spatial_buffered(
-        &mut self,
+        &self,
         source_handle: BevyHandle<Source>,
         settings: Source::Settings,
         spatial_options: SpatialOptions,
@@ -288,11 +483,36 @@ where
         rate: u32,
         buffer_duration: f32,
     ) -> BevyHandle<SpatialBufferedAudioSink<Source>> {
-        let stop_handle = HandleId::random::<SpatialBufferedAudioSink<Source>>();
-        let audio_to_play = AudioToPlay {
+        self.play_spatial_buffered_with_options(
             source_handle,
-            stop_handle,
             settings,
+            PlayOptions::default(),
+            spatial_options,
+            max_distance,
+            rate,
+            buffer_duration,
+        )
+    }
+
+    /// Play the given type that implements [`Signal`] with the given [`PlayOptions`].
+    ///
+    /// See [`Audio::play_spatial_buffered`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn play_spatial_buffered_with_options(
+        &self,
+        source_handle: BevyHandle<Source>,
+        settings: Source::Settings,
+        options: PlayOptions,
+        spatial_options: SpatialOptions,
+        max_distance: f32,
+        rate: u32,
+        buffer_duration: f32,
+    ) -> BevyHandle<SpatialBufferedAudioSink<Source>> {
+        let stop_handle = HandleId::random::<SpatialBufferedAudioSink<Source>>();
+        let audio_to_play = AudioToPlay {
+            source: PlaySource::Asset(source_handle, settings),
+            stop_handle: Some(stop_handle),
+            options,
             spatial_settings: Some(SpatialSettings {
                 options: spatial_options,
                 buffered_settings: Some(BufferedSettings {
@@ -302,7 +522,35 @@ where
                 }),
             }),
         };
-        self.queue.write().push_back(audio_to_play);
+        self.queue.push(audio_to_play);
         BevyHandle::<SpatialBufferedAudioSink<Source>>::weak(stop_handle)
     }
+
+    /// Play the given type that implements [`Signal`], without a sink to control it.
+    ///
+    /// See [`Audio::play_spatial_buffered`] and [`Audio::play_oneshot`].
+    pub fn play_spatial_buffered_oneshot(
+        &self,
+        source_handle: BevyHandle<Source>,
+        settings: Source::Settings,
+        spatial_options: SpatialOptions,
+        max_distance: f32,
+        rate: u32,
+        buffer_duration: f32,
+    ) {
+        let audio_to_play = AudioToPlay {
+            source: PlaySource::Asset(source_handle, settings),
+            stop_handle: None,
+            options: PlayOptions::default(),
+            spatial_settings: Some(SpatialSettings {
+                options: spatial_options,
+                buffered_settings: Some(BufferedSettings {
+                    max_distance,
+                    rate,
+                    buffer_duration,
+                }),
+            }),
+        };
+        self.queue.push(audio_to_play);
+    }
 }
@@ -0,0 +1,297 @@
+use {
+    crate::ToSignal,
+    bevy::reflect::{TypePath, TypeUuid},
+    oddio::{Controlled, Cycle, FixedGain, Frame, Frames, FramesSignal, Gain, Seek, Signal, Speed},
+    parking_lot::Mutex,
+    std::{ops::RangeInclusive, sync::Arc},
+};
+
+/// How a [`RandomSound`] picks the variation to play.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "sound_event", derive(serde::Deserialize))]
+pub enum Selection {
+    /// Pick any variation, possibly the same one twice in a row.
+    Random,
+    /// Play every variation once in a random order before any is repeated,
+    /// never playing the same variation twice in a row.
+    #[default]
+    Shuffle,
+    /// Play the variations in order, starting over after the last one.
+    Sequential,
+}
+
+struct SelectionState {
+    rng: fastrand::Rng,
+    /// Variations left to play in the current shuffle, popped from the back.
+    bag: Vec<usize>,
+    last: Option<usize>,
+}
+
+/// A container of sound variations, such as footsteps or impacts.
+///
+/// Every time it is played, a variation is picked according to its [`Selection`],
+/// and played with a random pitch and volume within the configured ranges.
+#[derive(TypeUuid, TypePath)]
+#[uuid = "5a7b9d2c-3e41-4f6a-8b0d-1c2e3f4a5b6c"]
+pub struct RandomSound<F: Frame> {
+    variations: Vec<Arc<Frames<F>>>,
+    selection: Selection,
+    pitch: RangeInclusive<f32>,
+    volume: RangeInclusive<f32>,
+    looping: bool,
+    state: Mutex<SelectionState>,
+}
+
+impl<F: Frame> RandomSound<F> {
+    /// Construct a `RandomSound` from the given variations.
+    ///
+    /// Variations are shuffled by default, and played at their original pitch and volume.
+    pub fn new(variations: impl IntoIterator<Item = Arc<Frames<F>>>) -> Self {
+        Self {
+            variations: variations.into_iter().collect(),
+            selection: Selection::default(),
+            pitch: 1.0..=1.0,
+            volume: 1.0..=1.0,
+            looping: false,
+            state: Mutex::new(SelectionState {
+                rng: fastrand::Rng::new(),
+                bag: Vec::new(),
+                last: None,
+            }),
+        }
+    }
+
+    /// Pick variations using the given [`Selection`].
+    #[must_use]
+    pub fn with_selection(mut self, selection: Selection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    /// Play each variation at a random speed factor within `pitch`.
+    ///
+    /// For example, `0.9..=1.1` varies the pitch by up to 10% in either direction.
+    #[must_use]
+    pub fn with_pitch(mut self, pitch: RangeInclusive<f32>) -> Self {
+        self.pitch = pitch;
+        self
+    }
+
+    /// Play each variation at a random volume within `volume`, as an amplitude ratio.
+    #[must_use]
+    pub fn with_volume(mut self, volume: RangeInclusive<f32>) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    /// Loop the picked variation until the sink is stopped.
+    #[must_use]
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Seed the random number generator, making the picked variations,
+    /// pitches, and volumes deterministic.
+    #[must_use]
+    pub fn with_seed(self, seed: u64) -> Self {
+        self.reseed(seed);
+        self
+    }
+
+    /// Reset the random number generator to the given seed.
+    ///
+    /// This also restarts the current shuffle or sequence.
+    pub fn reseed(&self, seed: u64) {
+        let mut state = self.state.lock();
+        state.rng = fastrand::Rng::with_seed(seed);
+        state.bag.clear();
+        state.last = None;
+    }
+
+    /// The variations of this sound.
+    #[must_use]
+    pub fn variations(&self) -> &[Arc<Frames<F>>] {
+        &self.variations
+    }
+
+    /// Pick the next of `len` variations, returning its index, speed factor, and volume.
+    fn pick(&self, len: usize) -> (usize, f32, f32) {
+        let mut state = self.state.lock();
+        let index = match self.selection {
+            _ if len <= 1 => 0,
+            Selection::Random => state.rng.usize(..len),
+            Selection::Shuffle => {
+                if state.bag.is_empty() {
+                    let mut bag: Vec<usize> = (0..len).collect();
+                    state.rng.shuffle(&mut bag);
+                    // The bag is popped from the back, so avoid starting a new
+                    // shuffle with the variation that ended the previous one.
+                    if bag.last() == state.last.as_ref() {
+                        bag.swap(0, len - 1);
+                    }
+                    state.bag = bag;
+                }
+                state.bag.pop().unwrap_or_default()
+            }
+            Selection::Sequential => state.last.map_or(0, |last| (last + 1) % len),
+        };
+        state.last = Some(index);
+        let pitch = lerp(&self.pitch, state.rng.f32());
+        let volume = lerp(&self.volume, state.rng.f32());
+        (index, pitch, volume)
+    }
+}
+
+fn lerp(range: &RangeInclusive<f32>, t: f32) -> f32 {
+    range.start() + (range.end() - range.start()) * t
+}
+
+impl<F: Frame + Copy> RandomSound<F> {
+    /// Silence that ends immediately.
+    pub(crate) fn silence() -> Gain<FixedGain<Speed<VariationSignal<F>>>> {
+        variation_signal(
+            VariationSignal::Once(FramesSignal::new(Frames::from_slice(1, &[]), 0.0)),
+            1.0,
+            1.0,
+        )
+    }
+}
+
+impl<F: Frame + Copy> RandomSound<F> {
+    /// Pick one of `len` variations, and create a signal playing the frames `variation` returns for it.
+    ///
+    /// This lets the variations be looked up when played, instead of kept in the `RandomSound`.
+    /// Variations without frames play silence that ends immediately.
+    pub(crate) fn play(
+        &self,
+        len: usize,
+        variation: impl FnOnce(usize) -> Option<Arc<Frames<F>>>,
+    ) -> Gain<FixedGain<Speed<VariationSignal<F>>>> {
+        let (index, pitch, volume) = self.pick(len);
+        let Some(frames) = variation(index) else {
+            return Self::silence();
+        };
+        let signal = if self.looping {
+            VariationSignal::Looping(Cycle::new(frames))
+        } else {
+            VariationSignal::Once(FramesSignal::new(frames, 0.0))
+        };
+        variation_signal(signal, pitch, volume)
+    }
+}
+
+fn variation_signal<F: Frame + Copy>(
+    signal: VariationSignal<F>,
+    pitch: f32,
+    volume: f32,
+) -> Gain<FixedGain<Speed<VariationSignal<F>>>> {
+    let speed = Speed::new(signal);
+    // SAFETY: the signal has not been shared yet, so this is the only control.
+    unsafe { Speed::make_control(&speed) }.set_speed(pitch);
+    Gain::new(FixedGain::new(speed, 20.0 * volume.log10()))
+}
+
+impl<F: Frame + Send + Sync + Copy> ToSignal for RandomSound<F> {
+    type Settings = ();
+    type Signal = Gain<FixedGain<Speed<VariationSignal<F>>>>;
+
+    /// Pick a variation and create its signal.
+    ///
+    /// A `RandomSound` without variations plays silence that ends immediately.
+    fn to_signal(&self, _settings: Self::Settings) -> Self::Signal {
+        self.play(self.variations.len(), |index| {
+            self.variations.get(index).cloned()
+        })
+    }
+}
+
+/// The signal of a single [`RandomSound`] variation.
+pub enum VariationSignal<F> {
+    /// The variation is played once.
+    Once(FramesSignal<F>),
+    /// The variation is looped forever.
+    Looping(Cycle<F>),
+}
+
+impl<F: Frame + Copy> Signal for VariationSignal<F> {
+    type Frame = F;
+
+    fn sample(&self, interval: f32, out: &mut [F]) {
+        match self {
+            VariationSignal::Once(signal) => signal.sample(interval, out),
+            VariationSignal::Looping(signal) => signal.sample(interval, out),
+        }
+    }
+
+    fn remaining(&self) -> f32 {
+        match self {
+            VariationSignal::Once(signal) => signal.remaining(),
+            VariationSignal::Looping(signal) => signal.remaining(),
+        }
+    }
+}
+
+impl<F: Frame + Copy> Seek for VariationSignal<F> {
+    fn seek(&self, seconds: f32) {
+        match self {
+            VariationSignal::Once(signal) => signal.seek(seconds),
+            VariationSignal::Looping(signal) => signal.seek(seconds),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use {
+        super::{RandomSound, Selection},
+        oddio::Frames,
+    };
+
+    fn sound(variations: usize) -> RandomSound<f32> {
+        RandomSound::new((0..variations).map(|_| Frames::from_slice(1, &[0.0]))).with_seed(42)
+    }
+
+    fn picks(sound: &RandomSound<f32>, n: usize) -> Vec<usize> {
+        (0..n)
+            .map(|_| sound.pick(sound.variations().len()).0)
+            .collect()
+    }
+
+    #[test]
+    fn sequential() {
+        let sound = sound(3).with_selection(Selection::Sequential);
+        assert_eq!(picks(&sound, 7), [0, 1, 2, 0, 1, 2, 0]);
+    }
+
+    #[test]
+    fn shuffle_never_repeats() {
+        let sound = sound(4).with_selection(Selection::Shuffle);
+        let picks = picks(&sound, 400);
+        for bag in picks.chunks(4) {
+            let mut bag = bag.to_vec();
+            bag.sort_unstable();
+            assert_eq!(bag, [0, 1, 2, 3]);
+        }
+        assert!(picks.windows(2).all(|pair| pair[0] != pair[1]));
+    }
+
+    #[test]
+    fn seeded_is_deterministic() {
+        let a = sound(5)
+            .with_selection(Selection::Random)
+            .with_pitch(0.8..=1.2)
+            .with_volume(0.5..=1.0);
+        let b = sound(5)
+            .with_selection(Selection::Random)
+            .with_pitch(0.8..=1.2)
+            .with_volume(0.5..=1.0);
+        for _ in 0..100 {
+            let (index, pitch, volume) = a.pick(5);
+            assert_eq!((index, pitch, volume), b.pick(5));
+            assert!((0.8..=1.2).contains(&pitch));
+            assert!((0.5..=1.0).contains(&volume));
+        }
+    }
+}
@@ -0,0 +1,537 @@
+use {
+    crate::{
+        frame_n,
+        random::{RandomSound, Selection},
+        SignalContext, ToSignal,
+    },
+    bevy::{
+        asset::{Handle, HandleUntyped, LoadState},
+        math::Vec3,
+        reflect::{TypePath, TypeUuid},
+    },
+    oddio::{Filter, Frame, Frames, Sample, Seek, Signal, SpatialOptions},
+    parking_lot::Mutex,
+    serde::Deserialize,
+    std::{
+        marker::PhantomData,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    },
+};
+
+/// Spatial settings of a [`SoundEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct SpatialSoundSettings {
+    /// Radius of the emitter. See [`SpatialOptions::radius`].
+    pub radius: f32,
+}
+
+/// Settings of a [`SoundEvent`], as written in a `*.sound.ron` file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoundEventSettings {
+    /// Name of the bus the event is routed through.
+    ///
+    /// `bevy_oddio` does not mix buses itself; this is left to the application.
+    pub bus: Option<String>,
+    /// Volume, as an amplitude ratio.
+    pub volume: f32,
+    /// Random deviation from `volume` on every play, in either direction.
+    pub volume_variation: f32,
+    /// Speed factor.
+    pub pitch: f32,
+    /// Random deviation from `pitch` on every play, in either direction.
+    pub pitch_variation: f32,
+    /// How the sound to play is picked.
+    pub selection: Selection,
+    /// Whether the sound loops until its sink is stopped.
+    pub looping: bool,
+    /// Spatial settings, if the event is played in the spatial scene.
+    pub spatial: Option<SpatialSoundSettings>,
+    /// Maximum number of instances of the event playing at once.
+    ///
+    /// Plays past the limit are skipped.
+    pub polyphony: Option<usize>,
+}
+
+impl Default for SoundEventSettings {
+    fn default() -> Self {
+        Self {
+            bus: None,
+            volume: 1.0,
+            volume_variation: 0.0,
+            pitch: 1.0,
+            pitch_variation: 0.0,
+            selection: Selection::default(),
+            looping: false,
+            spatial: None,
+            polyphony: None,
+        }
+    }
+}
+
+/// A sound defined in data, loaded from a `*.sound.ron` file.
+///
+/// ```ron
+/// (
+///     sounds: ["footstep_1.wav", "footstep_2.wav", "footstep_3.wav"],
+///     bus: Some("sfx"),
+///     volume: 0.8,
+///     volume_variation: 0.1,
+///     pitch_variation: 0.05,
+///     selection: Shuffle,
+///     polyphony: Some(4),
+///     spatial: Some((radius: 0.5)),
+/// )
+/// ```
+///
+/// Paths in `sounds` are relative to the `*.sound.ron` file. Every field except
+/// `sounds` is optional; see [`SoundEventSettings`].
+///
+/// Events without `spatial` settings load as a stereo `SoundEvent<[Sample; 2]>`, and are
+/// played with [`Audio::play`](crate::Audio::play). Events with `spatial` settings load as a
+/// mono `SoundEvent<Sample>`, and are played with
+/// [`Audio::play_spatial_buffered`](crate::Audio::play_spatial_buffered).
+///
+/// Plays of the event are deferred until all of its sounds are loaded. If any of them
+/// fails to load, the event plays silence instead. See [`SoundEvent::is_ready`].
+///
+/// Sounds are looked up whenever the event is played, so changes to them, such as
+/// hot reloading, are picked up by the next play. Sounds with the channels of the
+/// event are played from their own frames, and others from a copy converted to the
+/// channels of the event, made when they are first played.
+#[derive(TypeUuid, TypePath)]
+#[uuid = "8e3c1f4a-6b2d-4c7e-9a5f-0d1b2c3e4f5a"]
+pub struct SoundEvent<F: EventFrame> {
+    sounds: Vec<HandleUntyped>,
+    settings: SoundEventSettings,
+    /// Picks the sound to play, and its pitch and volume.
+    picker: RandomSound<F::Source>,
+    /// Sounds with other channels than the event, as last converted.
+    converted: Mutex<Vec<Option<Converted<F::Source>>>>,
+    voices: Arc<AtomicUsize>,
+}
+
+/// Frame of a [`SoundEvent`].
+///
+/// Stereo events are played as `[Sample; 2]`, and spatial events as mono [`Sample`]s.
+pub trait EventFrame: Frame + Copy + Send + Sync + 'static {
+    /// Frame of the [`AudioSource`](crate::AudioSource)s that are played without converting them.
+    type Source: Frame + Copy + Send + Sync + 'static;
+
+    /// The frames of the source with the channels of `Self::Source`, if that is the one loaded.
+    fn matching(
+        mono: Option<&Arc<Frames<[Sample; 1]>>>,
+        stereo: Option<&Arc<Frames<[Sample; 2]>>>,
+    ) -> Option<Arc<Frames<Self::Source>>>;
+
+    /// Sample a signal of `Self::Source` frames into `out`.
+    fn sample_source<T: Signal<Frame = Self::Source> + ?Sized>(
+        signal: &T,
+        interval: f32,
+        out: &mut [Self],
+    );
+}
+
+impl EventFrame for [Sample; 2] {
+    type Source = [Sample; 2];
+
+    fn matching(
+        _mono: Option<&Arc<Frames<[Sample; 1]>>>,
+        stereo: Option<&Arc<Frames<[Sample; 2]>>>,
+    ) -> Option<Arc<Frames<[Sample; 2]>>> {
+        stereo.cloned()
+    }
+
+    fn sample_source<T: Signal<Frame = [Sample; 2]> + ?Sized>(
+        signal: &T,
+        interval: f32,
+        out: &mut [Self],
+    ) {
+        signal.sample(interval, out);
+    }
+}
+
+impl EventFrame for Sample {
+    type Source = [Sample; 1];
+
+    fn matching(
+        mono: Option<&Arc<Frames<[Sample; 1]>>>,
+        _stereo: Option<&Arc<Frames<[Sample; 2]>>>,
+    ) -> Option<Arc<Frames<[Sample; 1]>>> {
+        mono.cloned()
+    }
+
+    fn sample_source<T: Signal<Frame = [Sample; 1]> + ?Sized>(
+        signal: &T,
+        interval: f32,
+        out: &mut [Self],
+    ) {
+        // SAFETY: `[Sample; 1]` has the same representation as `Sample`, and every
+        // length is a multiple of 1.
+        let out: &mut [[Sample; 1]] = unsafe { frame_n(out) };
+        signal.sample(interval, out);
+    }
+}
+
+/// An [`AudioSource`](crate::AudioSource) of a [`SoundEvent`].
+#[derive(Clone)]
+enum Source {
+    Mono(Arc<Frames<[Sample; 1]>>),
+    Stereo(Arc<Frames<[Sample; 2]>>),
+}
+
+impl Source {
+    fn ptr_eq(&self, other: &Source) -> bool {
+        match (self, other) {
+            (Source::Mono(a), Source::Mono(b)) => Arc::ptr_eq(a, b),
+            (Source::Stereo(a), Source::Stereo(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+/// Frames of a sound converted to the channels of the [`SoundEvent`] playing it.
+struct Converted<S> {
+    /// The frames converted, kept to tell when the sound changes.
+    source: Source,
+    frames: Arc<Frames<S>>,
+}
+
+impl<F: EventFrame> SoundEvent<F> {
+    /// Construct a `SoundEvent` playing the given [`AudioSource`](crate::AudioSource)s, which may be mono or stereo.
+    #[must_use]
+    pub fn new(sounds: Vec<HandleUntyped>, settings: SoundEventSettings) -> Self {
+        let SoundEventSettings {
+            volume,
+            volume_variation,
+            pitch,
+            pitch_variation,
+            selection,
+            looping,
+            ..
+        } = settings;
+        let picker = RandomSound::new([])
+            .with_selection(selection)
+            .with_volume((volume - volume_variation).max(0.0)..=volume + volume_variation)
+            .with_pitch((pitch - pitch_variation).max(0.0)..=pitch + pitch_variation)
+            .with_looping(looping);
+
+        Self {
+            converted: Mutex::new(sounds.iter().map(|_| None).collect()),
+            sounds,
+            settings,
+            picker,
+            voices: Arc::default(),
+        }
+    }
+
+    /// The sounds the event picks from.
+    #[must_use]
+    pub fn sounds(&self) -> &[HandleUntyped] {
+        &self.sounds
+    }
+
+    /// The settings of the event.
+    #[must_use]
+    pub fn settings(&self) -> &SoundEventSettings {
+        &self.settings
+    }
+
+    /// Name of the bus the event is routed through.
+    #[must_use]
+    pub fn bus(&self) -> Option<&str> {
+        self.settings.bus.as_deref()
+    }
+
+    /// Whether all sounds of the event are loaded, so that it can be played.
+    #[must_use]
+    pub fn is_ready(&self, context: &SignalContext) -> bool {
+        self.sounds
+            .iter()
+            .all(|sound| Self::source(sound, context).is_some())
+    }
+
+    /// Number of instances of the event currently playing.
+    #[must_use]
+    pub fn voices(&self) -> usize {
+        self.voices.load(Ordering::Relaxed)
+    }
+
+    /// Spatial options for playing the event at `position`, moving at `velocity`.
+    #[must_use]
+    pub fn spatial_options(&self, position: Vec3, velocity: Vec3) -> SpatialOptions {
+        SpatialOptions {
+            position: position.into(),
+            velocity: velocity.into(),
+            radius: self.settings.spatial.map_or(1.0, |spatial| spatial.radius),
+        }
+    }
+
+    fn source(sound: &HandleUntyped, context: &SignalContext) -> Option<Source> {
+        // Sounds may be of either type, so their handles are not converted with `typed`.
+        if let Some(source) = context.stereo(&Handle::weak(sound.id())) {
+            Some(Source::Stereo(source.frames.clone()))
+        } else {
+            context
+                .mono(&Handle::weak(sound.id()))
+                .map(|source| Source::Mono(source.frames.clone()))
+        }
+    }
+
+    /// The frames of a sound, converted to the channels of the event if needed.
+    fn frames(&self, index: usize, context: &SignalContext) -> Option<Arc<Frames<F::Source>>> {
+        let source = Self::source(self.sounds.get(index)?, context)?;
+        let matching = match &source {
+            Source::Mono(frames) => F::matching(Some(frames), None),
+            Source::Stereo(frames) => F::matching(None, Some(frames)),
+        };
+        if matching.is_some() {
+            return matching;
+        }
+
+        let mut converted = self.converted.lock();
+        let converted = converted.get_mut(index)?;
+        match converted {
+            Some(converted) if converted.source.ptr_eq(&source) => Some(converted.frames.clone()),
+            _ => {
+                let frames = match &source {
+                    Source::Mono(frames) => convert(frames),
+                    Source::Stereo(frames) => convert(frames),
+                };
+                *converted = Some(Converted {
+                    source,
+                    frames: frames.clone(),
+                });
+                Some(frames)
+            }
+        }
+    }
+}
+
+impl<F: EventFrame> ToSignal for SoundEvent<F> {
+    type Settings = ();
+    type Signal = Voice<F, <RandomSound<F::Source> as ToSignal>::Signal>;
+
+    fn is_loaded(&self, context: &SignalContext) -> bool {
+        self.is_ready(context)
+            || self
+                .sounds
+                .iter()
+                .any(|sound| context.load_state(sound) == LoadState::Failed)
+    }
+
+    /// Plays silence, as the sounds of the event can only be looked up through a [`SignalContext`].
+    ///
+    /// The play systems use [`to_signal_with_context`](ToSignal::to_signal_with_context) instead.
+    fn to_signal(&self, _settings: Self::Settings) -> Self::Signal {
+        Voice {
+            voices: None,
+            _frame: PhantomData,
+            inner: RandomSound::silence(),
+        }
+    }
+
+    fn to_signal_with_context(
+        &self,
+        _settings: Self::Settings,
+        context: &SignalContext,
+    ) -> Self::Signal {
+        let under_limit = self
+            .voices
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |voices| {
+                match self.settings.polyphony {
+                    Some(polyphony) if voices >= polyphony => None,
+                    _ => Some(voices + 1),
+                }
+            })
+            .is_ok();
+        if !under_limit {
+            return self.to_signal(());
+        }
+        Voice {
+            voices: Some(self.voices.clone()),
+            _frame: PhantomData,
+            inner: self
+                .picker
+                .play(self.sounds.len(), |index| self.frames(index, context)),
+        }
+    }
+}
+
+/// A playing instance of a [`SoundEvent`], counted towards its polyphony.
+pub struct Voice<F, T: ?Sized> {
+    voices: Option<Arc<AtomicUsize>>,
+    _frame: PhantomData<fn() -> F>,
+    inner: T,
+}
+
+impl<F, T: ?Sized> Drop for Voice<F, T> {
+    fn drop(&mut self) {
+        if let Some(voices) = &self.voices {
+            voices.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl<F: EventFrame, T: Signal<Frame = F::Source> + ?Sized> Signal for Voice<F, T> {
+    type Frame = F;
+
+    fn sample(&self, interval: f32, out: &mut [F]) {
+        F::sample_source(&self.inner, interval, out);
+    }
+
+    fn remaining(&self) -> f32 {
+        self.inner.remaining()
+    }
+
+    #[inline]
+    fn handle_dropped(&self) {
+        self.inner.handle_dropped();
+    }
+}
+
+impl<F: EventFrame, T: Seek + Signal<Frame = F::Source> + ?Sized> Seek for Voice<F, T> {
+    fn seek(&self, seconds: f32) {
+        self.inner.seek(seconds);
+    }
+}
+
+impl<F, T: ?Sized> Filter for Voice<F, T> {
+    type Inner = T;
+
+    fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+/// Convert frames to a different channel count, duplicating mono
+/// to every channel and averaging down to mono.
+fn convert<From: Frame + Copy, To: Frame>(frames: &Frames<From>) -> Arc<Frames<To>> {
+    #[allow(clippy::cast_precision_loss)]
+    Frames::from_iter(
+        frames.rate(),
+        frames.iter().map(|frame| {
+            let from = frame.channels();
+            let mut to = To::ZERO;
+            let channels = to.channels_mut();
+            if from.len() == 1 {
+                channels.fill(from[0]);
+            } else if channels.len() == 1 {
+                channels[0] = from.iter().sum::<Sample>() / from.len() as Sample;
+            } else {
+                for (to, from) in channels.iter_mut().zip(from) {
+                    *to = *from;
+                }
+            }
+            to
+        }),
+    )
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use {
+        super::{SoundEvent, SoundEventSettings},
+        crate::{random::Selection, AudioSource, SignalContext, ToSignal},
+        bevy::{
+            asset::{AddAsset, AssetPlugin, Assets, HandleId, HandleUntyped},
+            core::TaskPoolPlugin,
+            ecs::system::SystemState,
+            prelude::App,
+        },
+        oddio::{Frames, Sample, Signal},
+    };
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins((TaskPoolPlugin::default(), AssetPlugin::default()))
+            .add_asset::<AudioSource<[Sample; 1]>>()
+            .add_asset::<AudioSource<[Sample; 2]>>();
+        app
+    }
+
+    #[test]
+    fn plays_sounds_as_loaded() {
+        let mut app = app();
+        let stereo = app
+            .world
+            .resource_mut::<Assets<AudioSource<[Sample; 2]>>>()
+            .add(AudioSource::new(Frames::from_slice(1, &[[1.0, 2.0]])));
+        let mono = app
+            .world
+            .resource_mut::<Assets<AudioSource<[Sample; 1]>>>()
+            .add(AudioSource::new(Frames::from_slice(1, &[[3.0]])));
+        let event = SoundEvent::<[Sample; 2]>::new(
+            vec![stereo.clone_untyped(), mono.clone_untyped()],
+            SoundEventSettings {
+                selection: Selection::Sequential,
+                ..SoundEventSettings::default()
+            },
+        );
+        let mut state = SystemState::<SignalContext>::new(&mut app.world);
+
+        let context = state.get(&app.world);
+        assert!(event.is_ready(&context));
+        let mut out = [[0.0; 2]];
+        event
+            .to_signal_with_context((), &context)
+            .sample(0.0, &mut out);
+        assert_eq!(out, [[1.0, 2.0]]);
+        event
+            .to_signal_with_context((), &context)
+            .sample(0.0, &mut out);
+        assert_eq!(out, [[3.0, 3.0]]);
+
+        // Sounds with the channels of the event are not copied.
+        let frames = &context.stereo(&stereo).unwrap().frames;
+        let playing = event.to_signal_with_context((), &context);
+        assert_eq!(std::sync::Arc::strong_count(frames), 2);
+        drop(playing);
+
+        // Changed sounds are converted again.
+        app.world
+            .resource_mut::<Assets<AudioSource<[Sample; 1]>>>()
+            .set_untracked(&mono, AudioSource::new(Frames::from_slice(1, &[[4.0]])));
+        let context = state.get(&app.world);
+        event
+            .to_signal_with_context((), &context)
+            .sample(0.0, &mut out);
+        assert_eq!(out, [[4.0, 4.0]]);
+    }
+
+    #[test]
+    fn deferred_until_loaded() {
+        let mut app = app();
+        let mut state = SystemState::<SignalContext>::new(&mut app.world);
+        let handle = HandleUntyped::weak(HandleId::random::<AudioSource<[Sample; 1]>>());
+
+        let event = SoundEvent::<Sample>::new(vec![handle], SoundEventSettings::default());
+        assert!(!event.is_loaded(&state.get(&app.world)));
+    }
+
+    #[test]
+    fn polyphony() {
+        let mut app = app();
+        let mut state = SystemState::<SignalContext>::new(&mut app.world);
+        let context = state.get(&app.world);
+        let event = SoundEvent::<Sample>::new(
+            Vec::new(),
+            SoundEventSettings {
+                polyphony: Some(1),
+                ..SoundEventSettings::default()
+            },
+        );
+
+        let first = event.to_signal_with_context((), &context);
+        assert_eq!(event.voices(), 1);
+        let second = event.to_signal_with_context((), &context);
+        assert_eq!(event.voices(), 1);
+        assert!(second.remaining() <= 0.0);
+
+        drop(first);
+        assert_eq!(event.voices(), 0);
+    }
+}
@@ -0,0 +1,331 @@
+use {
+    crate::ToSignal,
+    bevy::reflect::{TypePath, TypeUuid},
+    oddio::{Controlled, Frame, Frames, Gain, Seek, Signal},
+    std::{
+        cell::Cell,
+        sync::{
+            atomic::{AtomicU32, Ordering},
+            Arc,
+        },
+    },
+};
+
+/// A stem of [`StemMusic`].
+pub struct Stem<F> {
+    frames: Arc<Frames<F>>,
+    activation: Option<(f32, f32)>,
+}
+
+impl<F> Stem<F> {
+    /// Construct a `Stem` that plays at full volume regardless of the parameter.
+    pub fn new(frames: Arc<Frames<F>>) -> Self {
+        Self {
+            frames,
+            activation: None,
+        }
+    }
+
+    /// Fade the stem in as the parameter rises from `silent` to `full`.
+    ///
+    /// If `silent` is greater than `full`, the stem fades out as the parameter rises instead.
+    #[must_use]
+    pub fn with_activation(mut self, silent: f32, full: f32) -> Self {
+        self.activation = Some((silent, full));
+        self
+    }
+}
+
+/// Music split into stems that play in lockstep, and are faded in and out
+/// individually based on a parameter, such as combat intensity.
+///
+/// All stems are mixed into a single signal that reads every stem at the same
+/// position, so they stay in sync through pausing, seeking, and looping.
+/// The parameter is set through [`StemsControl`]:
+///
+/// ```ignore
+/// sink.control::<Stems<[Sample; 2]>, _>().set_parameter(intensity);
+/// ```
+#[derive(TypeUuid, TypePath)]
+#[uuid = "e7a95c1b-2d48-4f3e-b06a-9c8d7e6f5a41"]
+pub struct StemMusic<F: Frame> {
+    stems: Vec<Stem<F>>,
+    looping: bool,
+    fade_time: f32,
+}
+
+impl<F: Frame> StemMusic<F> {
+    /// Construct looping `StemMusic` from the given stems, which fade over half a second.
+    pub fn new(stems: impl IntoIterator<Item = Stem<F>>) -> Self {
+        Self {
+            stems: stems.into_iter().collect(),
+            looping: true,
+            fade_time: 0.5,
+        }
+    }
+
+    /// Set whether the music loops.
+    #[must_use]
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Set how many seconds a stem takes to fade from silence to full volume.
+    #[must_use]
+    pub fn with_fade_time(mut self, fade_time: f32) -> Self {
+        self.fade_time = fade_time;
+        self
+    }
+}
+
+impl<F: Frame + Copy + Send + Sync> ToSignal for StemMusic<F> {
+    /// The initial value of the parameter.
+    type Settings = f32;
+    type Signal = Gain<Stems<F>>;
+
+    fn to_signal(&self, parameter: Self::Settings) -> Self::Signal {
+        let stems = Stems {
+            t: Cell::new(0.0),
+            length: self
+                .stems
+                .iter()
+                .map(|stem| stem.frames.runtime())
+                .fold(0.0, f64::max),
+            looping: self.looping,
+            fade_time: self.fade_time,
+            frames: self.stems.iter().map(|stem| stem.frames.clone()).collect(),
+            levels: self.stems.iter().map(|_| Cell::new(0.0)).collect(),
+            gains: self
+                .stems
+                .iter()
+                .map(|stem| StemGain {
+                    activation: stem.activation,
+                    target: AtomicU32::new(1.0_f32.to_bits()),
+                })
+                .collect(),
+        };
+        // SAFETY: the signal has not been shared yet, so this is the only control.
+        let mut control = unsafe { Stems::make_control(&stems) };
+        control.set_parameter(parameter);
+        for (level, gain) in stems.levels.iter().zip(&stems.gains) {
+            level.set(gain.target());
+        }
+        Gain::new(stems)
+    }
+}
+
+struct StemGain {
+    activation: Option<(f32, f32)>,
+    /// Volume the stem fades towards, as the bits of an `f32`.
+    target: AtomicU32,
+}
+
+impl StemGain {
+    fn target(&self) -> f32 {
+        f32::from_bits(self.target.load(Ordering::Relaxed))
+    }
+
+    fn set_target(&self, volume: f32) {
+        self.target.store(volume.to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// Mixes stems that are read at a shared position, each with its own fading volume.
+pub struct Stems<F> {
+    /// Playback position in seconds.
+    t: Cell<f64>,
+    /// Runtime of the longest stem.
+    length: f64,
+    looping: bool,
+    fade_time: f32,
+    frames: Vec<Arc<Frames<F>>>,
+    levels: Vec<Cell<f32>>,
+    gains: Vec<StemGain>,
+}
+
+impl<F> Stems<F> {
+    fn position(&self, t: f64) -> f64 {
+        if self.looping && self.length > 0.0 {
+            t.rem_euclid(self.length)
+        } else {
+            t
+        }
+    }
+}
+
+impl<F: Frame + Copy> Stems<F> {
+    /// Interpolate `frames` at `s` samples, wrapping to the first frame when looping.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn interpolate(&self, frames: &Frames<F>, s: f64) -> F {
+        let x0 = s.trunc() as usize;
+        let get = |x: usize| match frames.get(x) {
+            Some(frame) => *frame,
+            None if self.looping && x == frames.len() => frames.first().copied().unwrap_or(F::ZERO),
+            None => F::ZERO,
+        };
+        let (a, b) = (get(x0), get(x0 + 1));
+        let fract = s.fract() as f32;
+        let mut out = a;
+        for (out, b) in out.channels_mut().iter_mut().zip(b.channels()) {
+            *out += (b - *out) * fract;
+        }
+        out
+    }
+}
+
+impl<F: Frame + Copy> Signal for Stems<F> {
+    type Frame = F;
+
+    #[allow(clippy::cast_precision_loss)]
+    fn sample(&self, interval: f32, out: &mut [F]) {
+        let t = self.t.get();
+        let dt = f64::from(interval);
+        let step = if self.fade_time > 0.0 {
+            interval / self.fade_time
+        } else {
+            f32::INFINITY
+        };
+
+        out.fill(F::ZERO);
+        for ((frames, level), gain) in self.frames.iter().zip(&self.levels).zip(&self.gains) {
+            let target = gain.target();
+            let rate = f64::from(frames.rate());
+            let mut current = level.get();
+            for (i, out) in out.iter_mut().enumerate() {
+                current = if current < target {
+                    (current + step).min(target)
+                } else {
+                    (current - step).max(target)
+                };
+                if current == 0.0 {
+                    continue;
+                }
+                let frame = self.interpolate(frames, self.position(t + dt * i as f64) * rate);
+                for (out, sample) in out.channels_mut().iter_mut().zip(frame.channels()) {
+                    *out += sample * current;
+                }
+            }
+            level.set(current);
+        }
+
+        self.t.set(self.position(t + dt * out.len() as f64));
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn remaining(&self) -> f32 {
+        if self.looping {
+            f32::INFINITY
+        } else {
+            (self.length - self.t.get()) as f32
+        }
+    }
+}
+
+impl<F: Frame + Copy> Seek for Stems<F> {
+    fn seek(&self, seconds: f32) {
+        self.t.set(self.position(self.t.get() + f64::from(seconds)));
+    }
+}
+
+/// Thread-safe control for a [`Stems`] signal.
+pub struct StemsControl<'a>(&'a [StemGain]);
+
+// SAFETY: `StemsControl` only touches atomics.
+unsafe impl<'a, F: 'a> Controlled<'a> for Stems<F> {
+    type Control = StemsControl<'a>;
+
+    unsafe fn make_control(signal: &'a Stems<F>) -> Self::Control {
+        StemsControl(&signal.gains)
+    }
+}
+
+impl StemsControl<'_> {
+    /// Number of stems.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether there are no stems.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Fade every stem with an activation range to the volume for `parameter`.
+    ///
+    /// See [`Stem::with_activation`].
+    pub fn set_parameter(&mut self, parameter: f32) {
+        for gain in self.0 {
+            let Some((silent, full)) = gain.activation else {
+                continue;
+            };
+            let volume = if (full - silent).abs() <= f32::EPSILON {
+                if parameter >= full {
+                    1.0
+                } else {
+                    0.0
+                }
+            } else {
+                ((parameter - silent) / (full - silent)).clamp(0.0, 1.0)
+            };
+            gain.set_target(volume);
+        }
+    }
+
+    /// The volume the stem at `index` is fading towards, as an amplitude ratio.
+    #[must_use]
+    pub fn stem_volume(&self, index: usize) -> Option<f32> {
+        self.0.get(index).map(StemGain::target)
+    }
+
+    /// Fade the stem at `index` to `volume`, as an amplitude ratio.
+    pub fn set_stem_volume(&mut self, index: usize, volume: f32) {
+        if let Some(gain) = self.0.get(index) {
+            gain.set_target(volume);
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use {
+        super::{Stem, StemMusic, Stems},
+        crate::ToSignal,
+        oddio::{Controlled, Filter, Frames, Signal},
+    };
+
+    fn music() -> StemMusic<f32> {
+        StemMusic::new([
+            Stem::new(Frames::from_slice(4, &[1.0; 4])),
+            Stem::new(Frames::from_slice(4, &[2.0; 4])).with_activation(0.0, 1.0),
+        ])
+        .with_fade_time(0.5)
+    }
+
+    #[test]
+    fn parameter_fades_stems() {
+        let signal = music().to_signal(0.0);
+        let stems = signal.inner();
+        let mut buf = [0.0; 2];
+        stems.sample(0.25, &mut buf);
+        assert_eq!(buf, [1.0, 1.0]);
+
+        // SAFETY: only one control exists at a time in these tests.
+        unsafe { Stems::make_control(stems) }.set_parameter(1.0);
+        stems.sample(0.25, &mut buf);
+        assert_eq!(buf, [2.0, 3.0]);
+    }
+
+    #[test]
+    fn loops_in_sync() {
+        let signal = music().to_signal(1.0);
+        let stems = signal.inner();
+        let mut buf = [0.0; 6];
+        stems.sample(0.25, &mut buf);
+        assert_eq!(buf, [3.0; 6]);
+        assert!(stems.remaining().is_infinite());
+    }
+}
@@ -0,0 +1,745 @@
+use {
+    crate::{
+        loader::{stream_loader::StreamDecoder, AudioLoadError},
+        ToSignal,
+    },
+    bevy::reflect::{TypePath, TypeUuid},
+    crossbeam_channel::{Receiver, RecvTimeoutError, Sender, TryRecvError, TrySendError},
+    oddio::{Controlled, Frame, Gain, Sample, Seek, Signal, Speed},
+    std::{
+        cell::{Cell, RefCell},
+        marker::PhantomData,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, OnceLock,
+        },
+        time::Duration,
+    },
+};
+
+/// Number of frames decoded at once.
+const CHUNK_FRAMES: usize = 4096;
+/// Number of decoded chunks buffered ahead of playback.
+const BUFFERED_CHUNKS: usize = 8;
+/// How long the decoder thread waits for new streams when every stream is buffered.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Seek requests are packed into a single `u64`: the generation in the
+/// upper bits, and the frame to seek to in the lower [`FRAME_BITS`] bits.
+const FRAME_BITS: u32 = 40;
+const FRAME_MASK: u64 = (1 << FRAME_BITS) - 1;
+
+/// Encoding of a [`StreamingAudioSource`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StreamFormat {
+    /// FLAC, decoded with `claxon`.
+    #[cfg(feature = "flac")]
+    Flac,
+    /// MP3, decoded with `minimp3`.
+    #[cfg(feature = "mp3")]
+    Mp3,
+    /// Ogg Vorbis, decoded with `lewton`.
+    #[cfg(feature = "ogg")]
+    Ogg,
+    /// WAV, decoded with `hound`.
+    #[cfg(feature = "wav")]
+    Wav,
+}
+
+/// Source of audio that is decoded while it plays, instead of all at once when loaded.
+///
+/// Only the encoded file is kept in memory. Every time the source is played, a
+/// background thread shared by all streams decodes it a chunk at a time, staying
+/// a fraction of a second ahead of playback. This suits long music tracks, which
+/// would otherwise take up hundreds of megabytes once decoded.
+///
+/// Files are loaded as streaming sources when their extension is prefixed with
+/// `stream`, e.g. `theme.stream.ogg`. Mono files load as
+/// `StreamingAudioSource<[Sample; 1]>`, and stereo files as `StreamingAudioSource<[Sample; 2]>`.
+/// Files with more channels are downmixed while they play, as set with
+/// [`AudioPlugin::with_downmix`](crate::AudioPlugin::with_downmix).
+///
+/// Playback position can be read and changed through [`StreamingControl`]:
+///
+/// ```ignore
+/// sink.control::<Streaming<[Sample; 2]>, _>().seek_to(30.0);
+/// ```
+#[derive(TypeUuid, TypePath)]
+#[uuid = "6d1f9a3c-4b7e-4e2d-a8c5-2f0b9e7d3a61"]
+pub struct StreamingAudioSource<F: Frame> {
+    bytes: Arc<[u8]>,
+    format: StreamFormat,
+    sample_rate: u32,
+    channels: usize,
+    _frame: PhantomData<fn() -> F>,
+}
+
+impl<F: Frame> StreamingAudioSource<F> {
+    /// Construct a `StreamingAudioSource` from an encoded file with the given
+    /// sample rate and number of interleaved channels.
+    ///
+    /// Files with more channels than `F` are downmixed as with [`Downmix`](crate::Downmix).
+    #[must_use]
+    pub fn new(bytes: Arc<[u8]>, format: StreamFormat, sample_rate: u32, channels: usize) -> Self {
+        Self {
+            bytes,
+            format,
+            sample_rate,
+            channels,
+            _frame: PhantomData,
+        }
+    }
+
+    /// The encoding of the source.
+    #[must_use]
+    pub fn format(&self) -> StreamFormat {
+        self.format
+    }
+
+    /// The sample rate of the source.
+    #[must_use]
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// The number of channels in the encoded file.
+    #[must_use]
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+}
+
+/// Settings for playing a [`StreamingAudioSource`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StreamSettings {
+    /// Seconds into the source to start playing from.
+    pub start: f64,
+    /// Whether to start over after reaching the end.
+    pub looping: bool,
+}
+
+impl StreamSettings {
+    /// Generate settings for [`StreamingAudioSource`].
+    #[must_use]
+    pub fn new(start: f64, looping: bool) -> Self {
+        Self { start, looping }
+    }
+}
+
+impl<F: Frame + Copy + Send + Sync + 'static> ToSignal for StreamingAudioSource<F> {
+    type Settings = StreamSettings;
+    type Signal = Gain<Speed<Streaming<F>>>;
+
+    fn to_signal(&self, settings: Self::Settings) -> Self::Signal {
+        Gain::new(Speed::new(Streaming::new(self, settings)))
+    }
+}
+
+/// A block of decoded frames.
+struct Chunk<F> {
+    frames: Vec<F>,
+    /// Position of the first frame in the source.
+    start: u64,
+    /// Seek generation the chunk was decoded for.
+    generation: u64,
+    /// Whether this marks the end of the source.
+    end: bool,
+}
+
+/// State shared between a [`Streaming`] signal, its controls, and its decoder.
+struct Shared {
+    /// The latest seek request. See [`FRAME_BITS`].
+    seek: AtomicU64,
+    /// Frame being played.
+    position: AtomicU64,
+}
+
+impl Shared {
+    fn request_seek(&self, frame: u64) {
+        let generation = (self.seek.load(Ordering::Relaxed) >> FRAME_BITS) + 1;
+        self.seek.store(
+            (generation << FRAME_BITS) | (frame & FRAME_MASK),
+            Ordering::Release,
+        );
+    }
+
+    /// The generation and target frame of the latest seek.
+    fn seek(&self) -> (u64, u64) {
+        let seek = self.seek.load(Ordering::Acquire);
+        (seek >> FRAME_BITS, seek & FRAME_MASK)
+    }
+}
+
+/// Plays a [`StreamingAudioSource`] while it is decoded on a background thread.
+pub struct Streaming<F> {
+    rate: u32,
+    shared: Arc<Shared>,
+    chunks: Receiver<Chunk<F>>,
+    /// Returns spent chunk buffers to the decoder, so the audio thread never frees them.
+    recycle: Sender<Vec<F>>,
+    current: RefCell<Chunk<F>>,
+    /// Position within `current`, in fractional frames.
+    index: Cell<f64>,
+    /// The frame before `current`, to interpolate across chunks.
+    previous: Cell<F>,
+    ended: Cell<bool>,
+}
+
+impl<F: Frame + Copy + Send + 'static> Streaming<F> {
+    fn new(source: &StreamingAudioSource<F>, settings: StreamSettings) -> Self {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let start = (settings.start.max(0.0) * f64::from(source.sample_rate)) as u64;
+        let shared = Arc::new(Shared {
+            seek: AtomicU64::new(start & FRAME_MASK),
+            position: AtomicU64::new(start),
+        });
+        let (chunk_sender, chunks) = crossbeam_channel::bounded(BUFFERED_CHUNKS);
+        let (recycle, recycled) = crossbeam_channel::bounded(BUFFERED_CHUNKS + 2);
+
+        let decoder = Decoder {
+            bytes: source.bytes.clone(),
+            format: source.format,
+            looping: settings.looping,
+            shared: shared.clone(),
+            chunks: chunk_sender,
+            recycled,
+            stream: None,
+            // Start with a seek to `start`.
+            generation: u64::MAX,
+            position: 0,
+            skip: 0,
+            block: Vec::new(),
+            block_channels: 1,
+            used: 0,
+            frames: Vec::new(),
+            pending: None,
+            ended: false,
+        };
+        let _ = decoder_thread().send(Box::new(decoder));
+
+        Self {
+            rate: source.sample_rate,
+            shared,
+            chunks,
+            recycle,
+            current: RefCell::new(Chunk {
+                frames: Vec::new(),
+                start,
+                generation: 0,
+                end: false,
+            }),
+            index: Cell::new(0.0),
+            previous: Cell::new(F::ZERO),
+            ended: Cell::new(false),
+        }
+    }
+}
+
+impl<F: Frame + Copy> Streaming<F> {
+    /// Drop buffered frames from before the latest seek.
+    fn sync_seek(&self, current: &mut Chunk<F>) {
+        let (generation, frame) = self.shared.seek();
+        if current.generation != generation {
+            let frames = std::mem::take(&mut current.frames);
+            let _ = self.recycle.try_send(frames);
+            *current = Chunk {
+                frames: Vec::new(),
+                start: frame,
+                generation,
+                end: false,
+            };
+            self.index.set(0.0);
+            self.previous.set(F::ZERO);
+            self.ended.set(false);
+        }
+    }
+
+    /// Move on to the next chunk of the current generation.
+    ///
+    /// Returns `false` if none is decoded yet.
+    fn next_chunk(&self, current: &mut Chunk<F>) -> bool {
+        while let Ok(chunk) = self.chunks.try_recv() {
+            if chunk.generation != current.generation {
+                let _ = self.recycle.try_send(chunk.frames);
+                continue;
+            }
+            if let Some(last) = current.frames.last() {
+                self.previous.set(*last);
+            }
+            #[allow(clippy::cast_precision_loss)]
+            self.index
+                .set(self.index.get() - current.frames.len() as f64);
+            let spent = std::mem::replace(current, chunk);
+            let _ = self.recycle.try_send(spent.frames);
+            if current.end {
+                self.ended.set(true);
+            }
+            return true;
+        }
+        false
+    }
+}
+
+impl<F: Frame + Copy> Signal for Streaming<F> {
+    type Frame = F;
+
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    fn sample(&self, interval: f32, out: &mut [F]) {
+        let mut current = self.current.borrow_mut();
+        self.sync_seek(&mut current);
+        let step = f64::from(interval) * f64::from(self.rate);
+
+        for out in out.iter_mut() {
+            let mut i = self.index.get() as usize;
+            while i >= current.frames.len() && !self.ended.get() {
+                if !self.next_chunk(&mut current) {
+                    break;
+                }
+                i = self.index.get() as usize;
+            }
+            let frame = match current.frames.get(i) {
+                Some(&frame) => frame,
+                // Fade from the last frame of the source to silence.
+                None if self.ended.get() && i == current.frames.len() => F::ZERO,
+                None => {
+                    // The decoder has fallen behind, or the source has ended.
+                    *out = F::ZERO;
+                    continue;
+                }
+            };
+            let before = match i {
+                0 => self.previous.get(),
+                i => current.frames[i - 1],
+            };
+            let fract = self.index.get().fract() as f32;
+            *out = before;
+            for (out, sample) in out.channels_mut().iter_mut().zip(frame.channels()) {
+                *out += (sample - *out) * fract;
+            }
+            self.index.set(self.index.get() + step);
+        }
+
+        let played = current.start + (self.index.get() as u64).min(current.frames.len() as u64);
+        self.shared.position.store(played, Ordering::Relaxed);
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    fn remaining(&self) -> f32 {
+        if self.ended.get() {
+            // Frames are output a frame late, as they are interpolated from the one before.
+            let current = self.current.borrow();
+            let left = (current.frames.len() + 1) as f64 - self.index.get();
+            (left / f64::from(self.rate)) as f32
+        } else {
+            f32::INFINITY
+        }
+    }
+}
+
+impl<F: Frame + Copy> Seek for Streaming<F> {
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    fn seek(&self, seconds: f32) {
+        let position = self.shared.position.load(Ordering::Relaxed) as f64;
+        let target = position + f64::from(seconds) * f64::from(self.rate);
+        self.shared.request_seek(target.max(0.0) as u64);
+    }
+}
+
+/// Thread-safe control for a [`Streaming`] signal.
+pub struct StreamingControl<'a> {
+    shared: &'a Shared,
+    rate: u32,
+}
+
+// SAFETY: `StreamingControl` only touches atomics.
+unsafe impl<'a, F: 'a> Controlled<'a> for Streaming<F> {
+    type Control = StreamingControl<'a>;
+
+    unsafe fn make_control(signal: &'a Streaming<F>) -> Self::Control {
+        StreamingControl {
+            shared: &signal.shared,
+            rate: signal.rate,
+        }
+    }
+}
+
+impl StreamingControl<'_> {
+    /// Seconds into the source being played.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn position(&self) -> f64 {
+        self.shared.position.load(Ordering::Relaxed) as f64 / f64::from(self.rate)
+    }
+
+    /// Continue playback from `seconds` into the source.
+    ///
+    /// WAV and MP3 files skip straight to the new position, and Ogg Vorbis files to
+    /// the page holding it. FLAC files can't be seeked in, so seeking back in them
+    /// decodes them from the start again, and seeking forward decodes everything
+    /// in between. Playback is silent until the decoder catches up.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn seek_to(&mut self, seconds: f64) {
+        self.shared
+            .request_seek((seconds.max(0.0) * f64::from(self.rate)) as u64);
+    }
+}
+
+/// Progress of a [`Job`] on the decoder thread.
+enum Step {
+    /// The job decoded or handed out a chunk.
+    Busy,
+    /// The job is waiting for its signal to play what was decoded, or to seek.
+    Idle,
+    /// The signal was dropped.
+    Done,
+}
+
+/// Decoding of a stream, which the decoder thread takes turns at.
+trait Job: Send {
+    /// Decode or hand out up to a chunk, without blocking.
+    fn step(&mut self) -> Step;
+}
+
+/// Queue of the thread that decodes every playing stream.
+///
+/// One thread is shared by all streams, which only need to stay a fraction of a
+/// second ahead of playback, and decode a chunk at a time in turn.
+fn decoder_thread() -> &'static Sender<Box<dyn Job>> {
+    static JOBS: OnceLock<Sender<Box<dyn Job>>> = OnceLock::new();
+    JOBS.get_or_init(|| {
+        let (sender, receiver) = crossbeam_channel::unbounded::<Box<dyn Job>>();
+        let spawned = std::thread::Builder::new()
+            .name("bevy_oddio stream".into())
+            .spawn(move || {
+                let mut jobs = Vec::new();
+                loop {
+                    jobs.extend(receiver.try_iter());
+                    let mut busy = false;
+                    jobs.retain_mut(|job: &mut Box<dyn Job>| match job.step() {
+                        Step::Busy => {
+                            busy = true;
+                            true
+                        }
+                        Step::Idle => true,
+                        Step::Done => false,
+                    });
+                    if !busy {
+                        match receiver.recv_timeout(POLL_INTERVAL) {
+                            Ok(job) => jobs.push(job),
+                            Err(RecvTimeoutError::Timeout) => {}
+                            Err(RecvTimeoutError::Disconnected) => return,
+                        }
+                    }
+                }
+            });
+        if let Err(err) = spawned {
+            bevy::utils::tracing::error!("Cannot spawn stream decoder: {err:?}");
+        }
+        sender
+    })
+}
+
+/// Decodes a [`StreamingAudioSource`] for a [`Streaming`] signal on the decoder thread.
+struct Decoder<F> {
+    bytes: Arc<[u8]>,
+    format: StreamFormat,
+    looping: bool,
+    shared: Arc<Shared>,
+    chunks: Sender<Chunk<F>>,
+    recycled: Receiver<Vec<F>>,
+    /// Opened on the decoder thread, on the first step.
+    stream: Option<StreamDecoder>,
+    /// Seek generation being decoded for.
+    generation: u64,
+    /// Frame in the source of the next decoded frame.
+    position: u64,
+    /// Decoded frames to drop before the frame seeked to.
+    skip: u64,
+    /// The block being split into frames, and how many samples of it were used.
+    block: Vec<Sample>,
+    block_channels: usize,
+    used: usize,
+    /// The chunk being filled.
+    frames: Vec<F>,
+    /// A chunk that the signal had no room for yet.
+    pending: Option<Chunk<F>>,
+    /// Whether the end of the source was handed out.
+    ended: bool,
+}
+
+impl<F: Frame + Copy + Send> Job for Decoder<F> {
+    fn step(&mut self) -> Step {
+        let (generation, target) = self.shared.seek();
+        if generation != self.generation {
+            self.generation = generation;
+            self.pending = None;
+            self.ended = false;
+            self.frames.clear();
+            if let Err(err) = self.seek(target) {
+                self.fail(&err);
+            }
+        }
+
+        if let Some(chunk) = self.pending.take() {
+            return match self.chunks.try_send(chunk) {
+                Ok(()) => Step::Busy,
+                Err(TrySendError::Full(chunk)) => {
+                    self.pending = Some(chunk);
+                    Step::Idle
+                }
+                Err(TrySendError::Disconnected(_)) => Step::Done,
+            };
+        }
+        if self.ended {
+            // Wait for a seek, unless the signal was dropped.
+            return match self.recycled.try_recv() {
+                Err(TryRecvError::Disconnected) => Step::Done,
+                _ => Step::Idle,
+            };
+        }
+
+        if let Err(err) = self.fill() {
+            self.fail(&err);
+        }
+        Step::Busy
+    }
+}
+
+impl<F: Frame + Copy> Decoder<F> {
+    /// Continue decoding from `target`.
+    fn seek(&mut self, target: u64) -> Result<(), AudioLoadError> {
+        let channels = F::ZERO.channels().len();
+        let decoder = match &mut self.stream {
+            Some(decoder) => decoder,
+            None => self.stream.insert(StreamDecoder::new(
+                self.format,
+                self.bytes.clone(),
+                channels,
+            )?),
+        };
+        self.position = decoder.seek(target)?;
+        self.skip = target.saturating_sub(self.position);
+        self.block.clear();
+        self.used = 0;
+        Ok(())
+    }
+
+    /// Decode up to a chunk, leaving it to be handed out.
+    fn fill(&mut self) -> Result<(), AudioLoadError> {
+        if self.frames.capacity() == 0 {
+            self.frames = self.buffer();
+        }
+        while self.frames.len() < CHUNK_FRAMES {
+            if self.used == self.block.len() {
+                let Some(decoder) = &mut self.stream else {
+                    self.hand_out(true);
+                    return Ok(());
+                };
+                self.used = 0;
+                let Some(channels) = decoder.next_block(&mut self.block)? else {
+                    self.block.clear();
+                    return self.end();
+                };
+                self.block_channels = channels;
+            }
+
+            let frames = self.block[self.used..].chunks_exact(self.block_channels);
+            let wanted = CHUNK_FRAMES - self.frames.len();
+            for frame in frames.take(wanted) {
+                self.used += self.block_channels;
+                self.position += 1;
+                if self.skip > 0 {
+                    self.skip -= 1;
+                } else {
+                    self.frames.push(convert(frame));
+                }
+            }
+            // Drop any partial frame.
+            if self.block.len() - self.used < self.block_channels {
+                self.used = self.block.len();
+            }
+        }
+        self.hand_out(false);
+        Ok(())
+    }
+
+    /// Loop back to the start at the end of the source, or hand out its end.
+    fn end(&mut self) -> Result<(), AudioLoadError> {
+        let length = self.position;
+        if !self.looping || length == 0 {
+            self.hand_out(true);
+            return Ok(());
+        }
+        // Past the start, if the seek went beyond the end.
+        let target = (length + self.skip) % length;
+        if !self.frames.is_empty() {
+            self.hand_out(false);
+        }
+        self.seek(target)
+    }
+
+    /// Hand out the frames decoded so far as a chunk.
+    fn hand_out(&mut self, end: bool) {
+        let frames = std::mem::take(&mut self.frames);
+        self.pending = Some(Chunk {
+            start: self.position - frames.len() as u64,
+            frames,
+            generation: self.generation,
+            end,
+        });
+        self.ended = end;
+    }
+
+    /// Stop decoding after an error, ending the stream.
+    fn fail(&mut self, err: &AudioLoadError) {
+        bevy::utils::tracing::error!("Cannot decode audio stream: {err}");
+        self.stream = None;
+        self.hand_out(true);
+    }
+
+    fn buffer(&self) -> Vec<F> {
+        self.recycled.try_recv().map_or_else(
+            |_| Vec::with_capacity(CHUNK_FRAMES),
+            |mut frames| {
+                frames.clear();
+                frames
+            },
+        )
+    }
+}
+
+/// Convert interleaved samples to a frame, duplicating mono to every channel.
+fn convert<F: Frame>(samples: &[Sample]) -> F {
+    let mut frame = F::ZERO;
+    let channels = frame.channels_mut();
+    if samples.len() == 1 {
+        channels.fill(samples[0]);
+    } else {
+        for (channel, sample) in channels.iter_mut().zip(samples) {
+            *channel = *sample;
+        }
+    }
+    frame
+}
+
+#[cfg(all(test, feature = "wav"))]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use {
+        super::{StreamFormat, StreamSettings, Streaming, StreamingAudioSource},
+        oddio::{Sample, Signal},
+        std::{
+            io::Cursor,
+            time::{Duration, Instant},
+        },
+    };
+
+    /// Sample the next non-silent frame, waiting for the decoder to catch up.
+    fn next_sound(signal: &Streaming<[Sample; 1]>) -> Sample {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut out = [[0.0]];
+        while Instant::now() < deadline {
+            signal.sample(1.0 / 1000.0, &mut out);
+            if out[0][0] != 0.0 {
+                return out[0][0];
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        panic!("the decoder never caught up");
+    }
+
+    /// A mono source, whose frame `i` is `i / 32768` for `frames` frames.
+    fn ramp(frames: i16, sample_rate: u32) -> StreamingAudioSource<[Sample; 1]> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut bytes = Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut bytes, spec).unwrap();
+        for i in 0..frames {
+            writer.write_sample(i).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        StreamingAudioSource::new(bytes.into_inner().into(), StreamFormat::Wav, sample_rate, 1)
+    }
+
+    #[test]
+    fn starts_and_seeks() {
+        let source = ramp(10_000, 1000);
+        let signal = Streaming::new(&source, StreamSettings::new(2.0, false));
+        assert_eq!(next_sound(&signal), 2000.0 / 32768.0);
+        assert_eq!(next_sound(&signal), 2001.0 / 32768.0);
+
+        signal.shared.request_seek(5000);
+        assert_eq!(next_sound(&signal), 5000.0 / 32768.0);
+        assert_eq!(next_sound(&signal), 5001.0 / 32768.0);
+        assert!(signal.remaining().is_infinite());
+    }
+
+    #[test]
+    fn plays_every_frame_before_ending() {
+        // Sample exactly one frame at a time.
+        let signal = Streaming::new(&ramp(100, 1024), StreamSettings::default());
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut played = Vec::new();
+        let mut out = [[0.0]];
+        while signal.remaining() > 0.0 && Instant::now() < deadline {
+            signal.sample(1.0 / 1024.0, &mut out);
+            if out[0][0] == 0.0 {
+                std::thread::sleep(Duration::from_millis(1));
+            } else {
+                played.push(out[0][0]);
+            }
+        }
+        let expected = (1..100_i16).map(|i| f32::from(i) / 32768.0);
+        assert!(played.into_iter().eq(expected));
+    }
+
+    #[test]
+    fn downmixes_and_loops() {
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 1024,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut bytes = Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut bytes, spec).unwrap();
+        for i in 1..=3 {
+            writer.write_sample(i * 2).unwrap();
+            writer.write_sample(i * 4).unwrap();
+        }
+        writer.finalize().unwrap();
+        let source = StreamingAudioSource::<[Sample; 1]>::new(
+            bytes.into_inner().into(),
+            StreamFormat::Wav,
+            1024,
+            2,
+        );
+
+        let signal = Streaming::new(&source, StreamSettings::new(0.0, true));
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut out = [[0.0]; 6];
+        while out[0][0] == 0.0 && Instant::now() < deadline {
+            signal.sample(1.0 / 1024.0, &mut out[..1]);
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(out[0][0], 3.0 / 32768.0);
+        signal.sample(1.0 / 1024.0, &mut out);
+        let expected = [6.0, 9.0, 3.0, 6.0, 9.0, 3.0].map(|sample| [sample / 32768.0]);
+        assert_eq!(out, expected);
+        assert!(signal.remaining().is_infinite());
+    }
+}